@@ -0,0 +1,312 @@
+//! Audit log of the reactions the bot was asked to add, so moderators
+//! can see who made the bot react to what after the reactions are
+//! gone.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use serenity::model::{prelude::*, Timestamp};
+
+use crate::commands::{ReactToMessageWithReport, ReactionOutcome};
+
+/// Emoji that could not be added, with the reason.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditFailure {
+    /// Emoji as given by the user.
+    pub emoji: String,
+    /// Reason it was not added.
+    pub reason: String,
+}
+
+/// Outcome of a request, see [`ReactToMessageWithReport`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditOutcome {
+    /// Reactions that were added.
+    pub added: Vec<String>,
+    /// Emojis that could not be added.
+    pub failed: Vec<AuditFailure>,
+    /// Reason none of the reactions were attempted.
+    pub refused: Option<String>,
+}
+
+impl From<&ReactToMessageWithReport> for AuditOutcome {
+    fn from(report: &ReactToMessageWithReport) -> Self {
+        let (added, failed) = report.outcomes.iter().fold(
+            (Vec::new(), Vec::new()),
+            |(mut added, mut failed), (emoji, outcome)| {
+                match outcome {
+                    ReactionOutcome::Added(reaction_type) => added.push(reaction_type.to_string()),
+                    _ => failed.push(AuditFailure {
+                        emoji: emoji.clone(),
+                        reason: outcome.to_string(),
+                    }),
+                }
+                (added, failed)
+            },
+        );
+        let refused = report
+            .blocked
+            .as_ref()
+            .map(ToString::to_string)
+            .or_else(|| report.rate_limited.as_ref().map(ToString::to_string));
+        Self {
+            added,
+            failed,
+            refused,
+        }
+    }
+}
+
+impl std::fmt::Display for AuditOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(refused) = &self.refused {
+            return write!(f, "refused: {}", refused);
+        }
+        if !self.added.is_empty() {
+            write!(f, "added {}", self.added.join(" "))?;
+        }
+        if !self.failed.is_empty() {
+            if !self.added.is_empty() {
+                write!(f, ", ")?;
+            }
+            write!(
+                f,
+                "failed {}",
+                self.failed
+                    .iter()
+                    .map(|failure| format!("`{}` ({})", failure.emoji, failure.reason))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Request to react to a message made through the bot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Time of the request.
+    pub timestamp: Timestamp,
+    /// [`GuildId`] of the message.
+    pub guild_id: GuildId,
+    /// [`ChannelId`] of the message.
+    pub channel_id: ChannelId,
+    /// [`MessageId`] reacted to.
+    pub message_id: MessageId,
+    /// [`UserId`] of the user who asked for the reactions.
+    pub user_id: UserId,
+    /// Tag of the user at the time of the request.
+    pub user_tag: String,
+    /// Command as the user entered it.
+    pub command: String,
+    /// Emojis the user asked for.
+    pub emojis: String,
+    /// Outcome of the request.
+    pub outcome: AuditOutcome,
+}
+
+impl AuditEntry {
+    /// Entry as a single line of JSON.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("audit entry serializes to JSON")
+    }
+}
+
+impl std::fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "<t:{}:f> {} in {} on `{}`: `{}` - {}",
+            self.timestamp.unix_timestamp(),
+            self.user_id.mention(),
+            self.channel_id.mention(),
+            self.message_id,
+            self.command,
+            self.outcome,
+        )
+    }
+}
+
+/// Filters of [`AuditLog::query()`], [`None`] matching everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AuditQuery {
+    /// Requests made by the user.
+    pub user_id: Option<UserId>,
+    /// Requests reacting in the channel.
+    pub channel_id: Option<ChannelId>,
+    /// Requests reacting to the message.
+    pub message_id: Option<MessageId>,
+}
+
+impl AuditQuery {
+    /// Check if the entry matches the filters.
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.user_id.is_none_or(|user_id| entry.user_id == user_id)
+            && self
+                .channel_id
+                .is_none_or(|channel_id| entry.channel_id == channel_id)
+            && self
+                .message_id
+                .is_none_or(|message_id| entry.message_id == message_id)
+    }
+}
+
+/// Recent [`AuditEntry`]s of the guilds, capped per guild with the
+/// oldest entries dropped first.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    /// [`GuildId`] to its entries, oldest first.
+    guilds: HashMap<GuildId, VecDeque<AuditEntry>>,
+    /// Maximum number of entries kept per guild.
+    max_entries_per_guild: usize,
+}
+
+impl AuditLog {
+    /// Default maximum number of entries kept per guild.
+    pub const DEFAULT_MAX_ENTRIES_PER_GUILD: usize = 1_000;
+
+    /// Create a new [`AuditLog`] with the given cap.
+    pub fn new(max_entries_per_guild: usize) -> Self {
+        Self {
+            guilds: HashMap::new(),
+            max_entries_per_guild,
+        }
+    }
+
+    /// Maximum number of entries kept per guild.
+    pub fn max_entries_per_guild(&self) -> usize {
+        self.max_entries_per_guild
+    }
+
+    /// Append the entry, dropping the oldest entry of the guild if it
+    /// is full.
+    pub fn insert(&mut self, entry: AuditEntry) {
+        let entries = self.guilds.entry(entry.guild_id).or_default();
+        entries.push_back(entry);
+        while entries.len() > self.max_entries_per_guild {
+            entries.pop_front();
+        }
+    }
+
+    /// Entries of the guild matching the query in the channels that
+    /// are `visible`, newest first, at most `limit` of them.
+    pub fn query(
+        &self,
+        guild_id: GuildId,
+        query: &AuditQuery,
+        limit: usize,
+        visible: impl Fn(ChannelId) -> bool,
+    ) -> Vec<AuditEntry> {
+        self.guilds
+            .get(&guild_id)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .rev()
+                    .filter(|entry| query.matches(entry) && visible(entry.channel_id))
+                    .take(limit)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Drop the entries of the guild.
+    pub fn remove_guild(&mut self, guild_id: GuildId) {
+        self.guilds.remove(&guild_id);
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_ENTRIES_PER_GUILD)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::{prelude::*, Timestamp};
+
+    use super::{AuditEntry, AuditLog, AuditOutcome, AuditQuery};
+
+    fn entry(user_id: u64, message_id: u64) -> AuditEntry {
+        AuditEntry {
+            timestamp: Timestamp::from_unix_timestamp(1_700_000_000).unwrap(),
+            guild_id: GuildId(1),
+            channel_id: ChannelId(2),
+            message_id: MessageId(message_id),
+            user_id: UserId(user_id),
+            user_tag: format!("user#{}", user_id),
+            command: "/add_reaction emoji \"👍\"".to_string(),
+            emojis: "👍".to_string(),
+            outcome: AuditOutcome {
+                added: vec!["👍".to_string()],
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Entries are capped per guild and queried newest first.
+    #[test]
+    fn audit_log_01() {
+        let mut audit_log = AuditLog::new(3);
+        (1..=4).for_each(|message_id| audit_log.insert(entry(message_id % 2, message_id)));
+
+        let all = audit_log.query(GuildId(1), &AuditQuery::default(), 10, |_| true);
+        assert_eq!(
+            all.iter()
+                .map(|entry| entry.message_id.0)
+                .collect::<Vec<_>>(),
+            [4, 3, 2]
+        );
+
+        let query = AuditQuery {
+            user_id: Some(UserId(1)),
+            ..Default::default()
+        };
+        assert_eq!(
+            audit_log.query(GuildId(1), &query, 10, |_| true),
+            [entry(1, 3)]
+        );
+        assert!(audit_log
+            .query(GuildId(2), &AuditQuery::default(), 10, |_| true)
+            .is_empty());
+    }
+
+    /// Entries of the channels that are not visible are left out
+    /// before the limit applies.
+    #[test]
+    fn audit_log_03() {
+        let mut audit_log = AuditLog::default();
+        (1..=4).for_each(|message_id| {
+            audit_log.insert(AuditEntry {
+                channel_id: ChannelId(message_id % 2),
+                ..entry(1, message_id)
+            })
+        });
+
+        let visible = audit_log.query(GuildId(1), &AuditQuery::default(), 1, |channel_id| {
+            channel_id == ChannelId(1)
+        });
+        assert_eq!(
+            visible
+                .iter()
+                .map(|entry| entry.message_id.0)
+                .collect::<Vec<_>>(),
+            [3]
+        );
+        assert!(audit_log
+            .query(GuildId(1), &AuditQuery::default(), 10, |_| false)
+            .is_empty());
+    }
+
+    /// Entries round trip through JSON lines.
+    #[test]
+    fn audit_log_02() {
+        let entry = entry(1, 3);
+        let line = entry.to_json_line();
+        assert!(!line.contains('\n'));
+        assert_eq!(serde_json::from_str::<AuditEntry>(&line).unwrap(), entry);
+    }
+}
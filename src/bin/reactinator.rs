@@ -1,9 +1,13 @@
 //! Reactinator binary.
 
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc};
 
 use clap::Parser;
-use reactinator::Handler;
+use reactinator::{
+    rate_limit::{Limit, RateLimitConfig},
+    storage::SqliteStorage,
+    Handler,
+};
 use serenity::{model::prelude::*, Client};
 
 /// Reactinator
@@ -18,6 +22,60 @@ pub struct CommandLineArguments {
     /// token.
     #[arg(long, default_value("discord.token"))]
     pub discord_token_path: PathBuf,
+
+    /// Register the commands globally instead of per server.
+    #[arg(long)]
+    pub global_commands: bool,
+
+    /// Path to the SQLite database keeping the state across restarts.
+    /// The state only lives in memory if not given.
+    #[arg(long)]
+    pub database: Option<PathBuf>,
+
+    /// Read the content of the messages, needed to target messages by
+    /// their content. Message content is a privileged intent that must
+    /// be enabled for the bot in the developer portal.
+    #[arg(long)]
+    pub message_content: bool,
+
+    /// Commands a user can use, as `capacity/seconds`.
+    #[arg(long, value_name = "CAPACITY/SECONDS")]
+    pub user_command_limit: Option<Limit>,
+
+    /// Commands that can be used in a channel, as `capacity/seconds`.
+    #[arg(long, value_name = "CAPACITY/SECONDS")]
+    pub channel_command_limit: Option<Limit>,
+
+    /// Commands that can be used in a server, as `capacity/seconds`.
+    #[arg(long, value_name = "CAPACITY/SECONDS")]
+    pub guild_command_limit: Option<Limit>,
+
+    /// Reactions a user can add, as `capacity/seconds`.
+    #[arg(long, value_name = "CAPACITY/SECONDS")]
+    pub user_reaction_limit: Option<Limit>,
+
+    /// Reactions a single command can add.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_reactions_per_request: Option<u64>,
+}
+
+impl CommandLineArguments {
+    /// [`RateLimitConfig`] with the limits given overriding the
+    /// default ones.
+    pub fn rate_limit_config(&self) -> RateLimitConfig {
+        let default = RateLimitConfig::default();
+        RateLimitConfig {
+            user_commands: self.user_command_limit.unwrap_or(default.user_commands),
+            channel_commands: self
+                .channel_command_limit
+                .unwrap_or(default.channel_commands),
+            guild_commands: self.guild_command_limit.unwrap_or(default.guild_commands),
+            max_reactions_per_request: self
+                .max_reactions_per_request
+                .map_or(default.max_reactions_per_request, |max| max as usize),
+            user_reactions: self.user_reaction_limit.unwrap_or(default.user_reactions),
+        }
+    }
 }
 
 #[tokio::main]
@@ -31,6 +89,7 @@ async fn main() {
         command_line_arguments,
     );
 
+    let rate_limit_config = command_line_arguments.rate_limit_config();
     let token = command_line_arguments.discord_token.unwrap_or_else(|| {
         std::fs::read_to_string(&command_line_arguments.discord_token_path).unwrap_or_else(|err| {
             panic!(
@@ -41,13 +100,33 @@ async fn main() {
         })
     });
 
-    let mut client = Client::builder(
-        token,
-        GatewayIntents::non_privileged() | GatewayIntents::GUILD_MESSAGE_REACTIONS,
-    )
-    .event_handler(Handler::new())
-    .await
-    .expect("Couldn't create client");
+    let handler = match &command_line_arguments.database {
+        Some(database) => {
+            let storage = SqliteStorage::open(database).unwrap_or_else(|err| {
+                panic!(
+                    "unable to open database at `{}` due to `{}`",
+                    database.display(),
+                    err,
+                )
+            });
+            Handler::with_storage(Arc::new(storage))
+                .unwrap_or_else(|err| panic!("unable to load state due to `{}`", err))
+        }
+        None => Handler::new(),
+    }
+    .with_global_commands(command_line_arguments.global_commands)
+    .with_rate_limits(rate_limit_config)
+    .with_message_content(command_line_arguments.message_content);
+
+    let mut intents = GatewayIntents::non_privileged() | GatewayIntents::GUILD_MESSAGE_REACTIONS;
+    if command_line_arguments.message_content {
+        intents |= GatewayIntents::MESSAGE_CONTENT;
+    }
+
+    let mut client = Client::builder(token, intents)
+        .event_handler(handler)
+        .await
+        .expect("Couldn't create client");
 
     if let Err(err) = client.start().await {
         tracing::error!("client error: {}", err);
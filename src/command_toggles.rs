@@ -0,0 +1,152 @@
+//! Per guild choice of the commands that are enabled.
+//!
+//! Guilds only override the default of the commands, so commands
+//! added later follow their default until an admin changes them.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::{react_with, spell_on_message};
+
+/// Commands that can be enabled and disabled per guild, with whether
+/// they are enabled by default. `reactinator` is not one of them so
+/// that admins cannot lock themselves out.
+pub const TOGGLEABLE_COMMANDS: &[(&str, bool)] = &[
+    ("ping", false),
+    ("add_reaction", true),
+    ("list_custom_emojis", true),
+    ("reaction_history", true),
+    ("text_to_reactions", true),
+    (react_with::NAME, true),
+    (spell_on_message::NAME, true),
+    ("message_settings", true),
+    ("emoji", true),
+];
+
+/// Enabled commands of a guild.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandToggles {
+    /// Name of the command to whether it is enabled, for the commands
+    /// not following their default.
+    overrides: BTreeMap<String, bool>,
+}
+
+impl CommandToggles {
+    /// Toggles with every command enabled.
+    pub fn all_enabled() -> Self {
+        Self {
+            overrides: TOGGLEABLE_COMMANDS
+                .iter()
+                .filter(|(_, default)| !default)
+                .map(|(name, _)| (name.to_string(), true))
+                .collect(),
+        }
+    }
+
+    /// Check if the command is enabled, commands that cannot be
+    /// toggled always are.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        match default_enabled(name) {
+            Some(default) => self.overrides.get(name).copied().unwrap_or(default),
+            None => true,
+        }
+    }
+
+    /// Enable or disable the command.
+    pub fn set(&mut self, name: &str, enabled: bool) -> Result<(), ToggleError> {
+        let default =
+            default_enabled(name).ok_or_else(|| ToggleError::NotToggleable(name.to_string()))?;
+        if enabled == default {
+            self.overrides.remove(name);
+        } else {
+            self.overrides.insert(name.to_string(), enabled);
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for CommandToggles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = TOGGLEABLE_COMMANDS
+            .iter()
+            .map(|(name, default)| {
+                let enabled = self.is_enabled(name);
+                format!(
+                    "`{}`: {}{}",
+                    name,
+                    if enabled { "enabled" } else { "disabled" },
+                    if enabled == *default {
+                        ""
+                    } else {
+                        " (changed)"
+                    }
+                )
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Whether the command is enabled by default, [`None`] if it cannot
+/// be toggled.
+fn default_enabled(name: &str) -> Option<bool> {
+    TOGGLEABLE_COMMANDS
+        .iter()
+        .find(|(command_name, _)| *command_name == name)
+        .map(|(_, default)| *default)
+}
+
+/// Errors of the [`CommandToggles`].
+#[derive(Debug)]
+pub enum ToggleError {
+    /// Command is not one of the [`TOGGLEABLE_COMMANDS`].
+    NotToggleable(String),
+}
+
+impl std::fmt::Display for ToggleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToggleError::NotToggleable(name) => {
+                write!(f, "`{}` cannot be enabled or disabled", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ToggleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandToggles, ToggleError, TOGGLEABLE_COMMANDS};
+
+    /// Commands follow their default unless overridden, `reactinator`
+    /// stays enabled.
+    #[test]
+    fn command_toggles_01() {
+        let mut command_toggles = CommandToggles::default();
+        assert!(!command_toggles.is_enabled("ping"));
+        assert!(command_toggles.is_enabled("add_reaction"));
+        assert!(command_toggles.is_enabled("reactinator"));
+
+        command_toggles.set("ping", true).unwrap();
+        command_toggles.set("add_reaction", false).unwrap();
+        command_toggles.set("add_reaction", false).unwrap();
+        assert!(command_toggles.is_enabled("ping"));
+        assert!(!command_toggles.is_enabled("add_reaction"));
+        assert!(matches!(
+            command_toggles.set("reactinator", false),
+            Err(ToggleError::NotToggleable(_))
+        ));
+
+        command_toggles.set("ping", false).unwrap();
+        command_toggles.set("add_reaction", true).unwrap();
+        assert_eq!(command_toggles, CommandToggles::default());
+
+        let all_enabled = CommandToggles::all_enabled();
+        assert!(TOGGLEABLE_COMMANDS
+            .iter()
+            .all(|(name, _)| all_enabled.is_enabled(name)));
+    }
+}
@@ -0,0 +1,291 @@
+//! `reactinator commands` subcommands to enable and disable the
+//! commands in the guild, see [`CommandToggles`].
+
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommandOption,
+    model::{
+        application::interaction::application_command::{
+            ApplicationCommandInteraction, CommandDataOption,
+        },
+        prelude::*,
+    },
+};
+
+use crate::{
+    command_toggles::{CommandToggles, ToggleError, TOGGLEABLE_COMMANDS},
+    BotContext,
+};
+
+use super::{
+    options::{command_options, OptionError},
+    subcommands::{Subcommand, SubcommandGroup},
+};
+
+/// Register the `commands` subcommands in the group.
+pub fn register(group: &mut SubcommandGroup, bot_context: &BotContext) {
+    group
+        .subcommand::<List>(bot_context)
+        .subcommand::<Enable>(bot_context)
+        .subcommand::<Disable>(bot_context)
+        .subcommand::<Reset>(bot_context);
+}
+
+/// Option `command`.
+const OPTION_COMMAND: &str = "command";
+
+/// Add the commands that can be toggled as choices to the option.
+pub fn command_choices(
+    option: &mut CreateApplicationCommandOption,
+) -> &mut CreateApplicationCommandOption {
+    TOGGLEABLE_COMMANDS.iter().for_each(|(name, _)| {
+        option.add_string_choice(name, name);
+    });
+    option
+}
+
+command_options! {
+    /// Options of the `reactinator commands enable` and `reactinator
+    /// commands disable` subcommands.
+    struct CommandOptions {
+        /// Command to enable or disable.
+        command(OPTION_COMMAND): String => command_choices,
+    }
+}
+
+/// `reactinator commands list` subcommand.
+pub struct List;
+
+#[async_trait]
+impl Subcommand for List {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("list")
+            .description("Show which commands are enabled in this server.");
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        _options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let command_toggles = match command_interaction.guild_id {
+            Some(guild_id) => Ok(bot_context.get_command_toggles(guild_id).await),
+            None => Err(Error::NotInGuild),
+        };
+        respond(command_interaction, context, command_toggles).await;
+    }
+}
+
+/// `reactinator commands enable` subcommand.
+pub struct Enable;
+
+#[async_trait]
+impl Subcommand for Enable {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("enable")
+            .description("Enable a command in this server.");
+        CommandOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let command_toggles = async {
+            let options = CommandOptions::parse(options)?;
+            set_command(command_interaction, bot_context, &options.command, true).await
+        }
+        .await;
+        respond(command_interaction, context, command_toggles).await;
+    }
+}
+
+/// `reactinator commands disable` subcommand.
+pub struct Disable;
+
+#[async_trait]
+impl Subcommand for Disable {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("disable")
+            .description("Disable a command in this server.");
+        CommandOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let command_toggles = async {
+            let options = CommandOptions::parse(options)?;
+            set_command(command_interaction, bot_context, &options.command, false).await
+        }
+        .await;
+        respond(command_interaction, context, command_toggles).await;
+    }
+}
+
+/// `reactinator commands reset` subcommand.
+pub struct Reset;
+
+#[async_trait]
+impl Subcommand for Reset {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("reset")
+            .description("Enable the default commands only.");
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        _options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let command_toggles = async {
+            let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+            let command_toggles = bot_context
+                .update_command_toggles(guild_id, |command_toggles| {
+                    *command_toggles = CommandToggles::default();
+                    Ok(())
+                })
+                .await?;
+            tracing::info!(
+                target: "commands",
+                "user `{}` - reset the commands of guild `{}`",
+                command_interaction.user.tag(),
+                guild_id,
+            );
+            Ok(command_toggles)
+        }
+        .await;
+        respond(command_interaction, context, command_toggles).await;
+    }
+}
+
+/// Enable or disable the command in the guild, returning the updated
+/// [`CommandToggles`].
+async fn set_command(
+    command_interaction: &ApplicationCommandInteraction,
+    bot_context: &BotContext,
+    command_name: &str,
+    enabled: bool,
+) -> Result<CommandToggles, Error> {
+    let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+    let command_name = command_name.trim().trim_start_matches('/');
+    let command_toggles = bot_context
+        .update_command_toggles(guild_id, |command_toggles| {
+            command_toggles.set(command_name, enabled)
+        })
+        .await?;
+
+    tracing::info!(
+        target: "commands",
+        "user `{}` - {} `{}` in guild `{}`",
+        command_interaction.user.tag(),
+        if enabled { "enabled" } else { "disabled" },
+        command_name,
+        guild_id,
+    );
+    Ok(command_toggles)
+}
+
+/// Respond with the enabled commands or the error.
+async fn respond(
+    command_interaction: &ApplicationCommandInteraction,
+    context: &serenity::prelude::Context,
+    command_toggles: Result<CommandToggles, Error>,
+) {
+    let response_content = match command_toggles {
+        Ok(command_toggles) => command_toggles.to_string(),
+        Err(err) => {
+            tracing::error!(
+                target: "commands",
+                "user `{}` - {}",
+                command_interaction.user.tag(),
+                err,
+            );
+            format!("error: {}", err)
+        }
+    };
+
+    if let Err(err) = command_interaction
+        .create_interaction_response(&context.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message.content(response_content).ephemeral(true)
+                })
+        })
+        .await
+    {
+        tracing::error!(
+            "couldn't respond to slash command for user `{}` due to `{}`",
+            command_interaction.user.tag(),
+            err,
+        );
+    }
+}
+
+/// `reactinator commands` related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Used outside of a guild.
+    NotInGuild,
+    /// Command cannot be enabled or disabled.
+    Toggle(ToggleError),
+    /// Options are missing or invalid.
+    Options(OptionError),
+}
+
+impl From<ToggleError> for Error {
+    fn from(err: ToggleError) -> Self {
+        Error::Toggle(err)
+    }
+}
+
+impl From<OptionError> for Error {
+    fn from(err: OptionError) -> Self {
+        Error::Options(err)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reactinator commands: ")?;
+        match self {
+            Error::NotInGuild => write!(f, "can only be used in a server"),
+            Error::Toggle(err) => write!(f, "{}", err),
+            Error::Options(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
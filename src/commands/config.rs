@@ -0,0 +1,333 @@
+//! `reactinator config` subcommands to view and change the
+//! configuration of the guild, see [`GuildConfig`].
+
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommandOption,
+    json::Value,
+    model::{
+        application::{
+            command::CommandOptionType,
+            interaction::application_command::{ApplicationCommandInteraction, CommandDataOption},
+        },
+        prelude::*,
+    },
+};
+
+use crate::{
+    config::{ConfigError, ConfigKey, GuildConfig},
+    BotContext,
+};
+
+use super::{
+    options::{command_options, OptionError, OptionValue, OptionValueError},
+    subcommands::{Subcommand, SubcommandGroup},
+};
+
+/// Register the `config` subcommands in the group.
+pub fn register(group: &mut SubcommandGroup, bot_context: &BotContext) {
+    group
+        .subcommand::<Get>(bot_context)
+        .subcommand::<Set>(bot_context)
+        .subcommand::<Reset>(bot_context)
+        .subcommand::<List>(bot_context);
+}
+
+/// Option `key`.
+const OPTION_KEY: &str = "key";
+
+/// Option `value`.
+const OPTION_VALUE: &str = "value";
+
+/// Add the keys as choices to the option.
+fn key_choices(option: &mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption {
+    ConfigKey::ALL.into_iter().for_each(|key| {
+        option.add_string_choice(key.name(), key.name());
+    });
+    option
+}
+
+impl OptionValue for ConfigKey {
+    const KIND: CommandOptionType = CommandOptionType::String;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_str()
+            .and_then(ConfigKey::parse)
+            .ok_or(OptionValueError::Expected("a configuration key"))
+    }
+}
+
+command_options! {
+    /// Options of the `reactinator config get` and `reactinator config
+    /// reset` subcommands.
+    struct KeyOptions {
+        /// Configuration key.
+        key(OPTION_KEY): ConfigKey => key_choices,
+    }
+}
+
+/// `reactinator config get` subcommand.
+pub struct Get;
+
+#[async_trait]
+impl Subcommand for Get {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("get")
+            .description("Show the value of a configuration key.");
+        KeyOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let content = async {
+            let options = KeyOptions::parse(options)?;
+            let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+            let guild_config = bot_context.get_guild_config(Some(guild_id)).await;
+            Ok(key_content(&guild_config, options.key))
+        }
+        .await;
+        respond(command_interaction, context, content).await;
+    }
+}
+
+command_options! {
+    /// Options of the `reactinator config set` subcommand.
+    struct SetOptions {
+        /// Configuration key.
+        key(OPTION_KEY): ConfigKey => key_choices,
+        /// New value, see `reactinator config list` for the accepted
+        /// values.
+        value(OPTION_VALUE): String,
+    }
+}
+
+/// `reactinator config set` subcommand.
+pub struct Set;
+
+#[async_trait]
+impl Subcommand for Set {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("set")
+            .description("Change the value of a configuration key.");
+        SetOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let content = async {
+            let options = SetOptions::parse(options)?;
+            let guild_config = update_config(command_interaction, bot_context, |guild_config| {
+                guild_config.set(options.key, &options.value)
+            })
+            .await?;
+            Ok(key_content(&guild_config, options.key))
+        }
+        .await;
+        respond(command_interaction, context, content).await;
+    }
+}
+
+/// `reactinator config reset` subcommand.
+pub struct Reset;
+
+#[async_trait]
+impl Subcommand for Reset {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("reset")
+            .description("Reset a configuration key to its default.");
+        KeyOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let content = async {
+            let options = KeyOptions::parse(options)?;
+            let guild_config = update_config(command_interaction, bot_context, |guild_config| {
+                guild_config.reset(options.key);
+                Ok(())
+            })
+            .await?;
+            Ok(key_content(&guild_config, options.key))
+        }
+        .await;
+        respond(command_interaction, context, content).await;
+    }
+}
+
+/// `reactinator config list` subcommand.
+pub struct List;
+
+#[async_trait]
+impl Subcommand for List {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("list")
+            .description("Show every configuration key with its value.");
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        _options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let content = async {
+            let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+            let guild_config = bot_context.get_guild_config(Some(guild_id)).await;
+            Ok(ConfigKey::ALL
+                .into_iter()
+                .map(|key| key_content(&guild_config, key))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        .await;
+        respond(command_interaction, context, content).await;
+    }
+}
+
+/// Value of the key with what it changes and the values it accepts.
+fn key_content(guild_config: &GuildConfig, key: ConfigKey) -> String {
+    let value = guild_config.get(key);
+    let default = GuildConfig::default().get(key);
+    let value = if value == default {
+        format!("`{}`", value)
+    } else {
+        format!("`{}` (default `{}`)", value, default)
+    };
+    format!(
+        "**{}**: {}\n{}, {}.",
+        key,
+        value,
+        key.description(),
+        key.expected()
+    )
+}
+
+/// Apply `update` to the configuration of the guild, returning the
+/// updated configuration.
+async fn update_config(
+    command_interaction: &ApplicationCommandInteraction,
+    bot_context: &BotContext,
+    update: impl FnOnce(&mut GuildConfig) -> Result<(), ConfigError>,
+) -> Result<GuildConfig, Error> {
+    let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+    let guild_config = bot_context.update_guild_config(guild_id, update).await?;
+
+    tracing::info!(
+        target: "config",
+        "user `{}` - config of guild `{}` is now {:?}",
+        command_interaction.user.tag(),
+        guild_id,
+        guild_config,
+    );
+    Ok(guild_config)
+}
+
+/// Respond with the content or the error.
+async fn respond(
+    command_interaction: &ApplicationCommandInteraction,
+    context: &serenity::prelude::Context,
+    content: Result<String, Error>,
+) {
+    let response_content = match content {
+        Ok(content) => content,
+        Err(err) => {
+            tracing::error!(
+                target: "config",
+                "user `{}` - {}",
+                command_interaction.user.tag(),
+                err,
+            );
+            format!("error: {}", err)
+        }
+    };
+
+    if let Err(err) = command_interaction
+        .create_interaction_response(&context.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message.content(response_content).ephemeral(true)
+                })
+        })
+        .await
+    {
+        tracing::error!(
+            "couldn't respond to slash command for user `{}` due to `{}`",
+            command_interaction.user.tag(),
+            err,
+        );
+    }
+}
+
+/// `reactinator config` related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Used outside of a guild.
+    NotInGuild,
+    /// Value is invalid for the key.
+    Config(ConfigError),
+    /// Options are missing or invalid.
+    Options(OptionError),
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        Error::Config(err)
+    }
+}
+
+impl From<OptionError> for Error {
+    fn from(err: OptionError) -> Self {
+        Error::Options(err)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reactinator config: ")?;
+        match self {
+            Error::NotInGuild => write!(f, "can only be used in a server"),
+            Error::Config(err) => write!(f, "{}", err),
+            Error::Options(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,147 @@
+//! Command grouping the emoji related subcommands.
+
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateApplicationCommandOption},
+    model::{
+        application::interaction::{
+            application_command::{ApplicationCommandInteraction, CommandDataOption},
+            autocomplete::AutocompleteInteraction,
+        },
+        prelude::InteractionResponseType,
+    },
+};
+
+use crate::{emojis::suggest_emojis, BotContext};
+
+use super::{
+    options::command_options,
+    subcommands::{Subcommand, Subcommands},
+    Command,
+};
+
+/// `emoji` command.
+pub struct Emoji {
+    /// Subcommands of the command.
+    subcommands: Subcommands,
+}
+
+#[async_trait]
+impl Command for Emoji {
+    fn register(command: &mut CreateApplicationCommand, bot_context: &BotContext) -> Self {
+        command.name("emoji").description("Emoji related commands.");
+        let mut subcommands = Subcommands::new();
+        subcommands.subcommand::<Search>(command, bot_context);
+        Self { subcommands }
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        self.subcommands
+            .interaction(command_interaction, context, bot_context)
+            .await;
+    }
+
+    async fn autocomplete(
+        &self,
+        autocomplete_interaction: &AutocompleteInteraction,
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        self.subcommands
+            .autocomplete(autocomplete_interaction, context, bot_context)
+            .await;
+    }
+}
+
+/// `emoji search` subcommand.
+pub struct Search;
+
+/// Option `query`.
+const OPTION_QUERY: &str = "query";
+
+command_options! {
+    /// Options of the `emoji search` subcommand.
+    struct SearchOptions {
+        /// Name of the emoji, or part of it.
+        query(OPTION_QUERY): String,
+    }
+}
+
+impl Search {
+    /// Maximum number of emojis listed.
+    const MAX_RESULTS: usize = 25;
+}
+
+#[async_trait]
+impl Subcommand for Search {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("search")
+            .description("Search the emojis available to react with.");
+        SearchOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let response_content = match SearchOptions::parse(options) {
+            Ok(options) => {
+                let suggestions = suggest_emojis(
+                    &options.query,
+                    command_interaction.guild_id,
+                    &*bot_context.emoji_index.read().await,
+                    Self::MAX_RESULTS,
+                );
+                if suggestions.is_empty() {
+                    format!("No emojis matching `{}`", options.query)
+                } else {
+                    suggestions
+                        .into_iter()
+                        .map(|suggestion| format!("{} - `{}`", suggestion.emoji, suggestion.name))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+            Err(err) => {
+                tracing::error!(
+                    target: "emoji",
+                    "user `{}` - emoji search: {}",
+                    command_interaction.user.tag(),
+                    err
+                );
+                format!("error: emoji search: {}", err)
+            }
+        };
+
+        if let Err(err) = command_interaction
+            .create_interaction_response(&context.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message.content(response_content).ephemeral(true)
+                    })
+            })
+            .await
+        {
+            tracing::error!(
+                target: "emoji",
+                "couldn't respond to `emoji search` for user `{}` due to `{}`",
+                command_interaction.user.tag(),
+                err
+            );
+        }
+    }
+}
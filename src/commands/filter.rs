@@ -0,0 +1,308 @@
+//! `reactinator filter` subcommands to view and change the words and
+//! emojis the bot refuses to react with, see [`ContentFilter`].
+
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommandOption,
+    model::{
+        application::interaction::application_command::{
+            ApplicationCommandInteraction, CommandDataOption,
+        },
+        prelude::*,
+    },
+};
+
+use crate::{
+    content_filter::{ContentFilter, FilterEntry},
+    emojis::resolve_emoji,
+    BotContext,
+};
+
+use super::{
+    options::{command_options, OptionError},
+    subcommands::{Subcommand, SubcommandGroup},
+};
+
+/// Register the `filter` subcommands in the group.
+pub fn register(group: &mut SubcommandGroup, bot_context: &BotContext) {
+    group
+        .subcommand::<Show>(bot_context)
+        .subcommand::<Add>(bot_context)
+        .subcommand::<Remove>(bot_context)
+        .subcommand::<Defaults>(bot_context);
+}
+
+/// Option `entry`.
+const OPTION_ENTRY: &str = "entry";
+
+/// Option `enabled`.
+const OPTION_ENABLED: &str = "enabled";
+
+/// `reactinator filter show` subcommand.
+pub struct Show;
+
+#[async_trait]
+impl Subcommand for Show {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("show")
+            .description("Show the words and emojis the bot refuses to react with.");
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        _options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let content_filter = match command_interaction.guild_id {
+            Some(guild_id) => Ok(bot_context.get_content_filter(Some(guild_id)).await),
+            None => Err(Error::NotInGuild),
+        };
+        respond(command_interaction, context, content_filter).await;
+    }
+}
+
+command_options! {
+    /// Options of the `reactinator filter add` and `reactinator filter
+    /// remove` subcommands.
+    struct EntryOptions {
+        /// Word or emoji, words also match their leetspeak spellings.
+        entry(OPTION_ENTRY): String,
+    }
+}
+
+impl EntryOptions {
+    /// Parse the entry, resolving emojis of the form `:name:`.
+    async fn filter_entry(
+        &self,
+        guild_id: GuildId,
+        bot_context: &BotContext,
+    ) -> Result<FilterEntry, Error> {
+        let entry = resolve_emoji(
+            self.entry.trim(),
+            Some(guild_id),
+            &*bot_context.emoji_index.read().await,
+        );
+        FilterEntry::parse(&entry).ok_or(Error::InvalidEntry)
+    }
+}
+
+/// `reactinator filter add` subcommand.
+pub struct Add;
+
+#[async_trait]
+impl Subcommand for Add {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("add")
+            .description("Refuse to react with a word or emoji.");
+        EntryOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let content_filter = async {
+            let options = EntryOptions::parse(options)?;
+            let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+            let entry = options.filter_entry(guild_id, bot_context).await?;
+            update_filter(command_interaction, bot_context, |content_filter| {
+                content_filter.add(entry);
+            })
+            .await
+        }
+        .await;
+        respond(command_interaction, context, content_filter).await;
+    }
+}
+
+/// `reactinator filter remove` subcommand.
+pub struct Remove;
+
+#[async_trait]
+impl Subcommand for Remove {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("remove")
+            .description("Stop refusing a word or emoji added before.");
+        EntryOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let content_filter = async {
+            let options = EntryOptions::parse(options)?;
+            let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+            let entry = options.filter_entry(guild_id, bot_context).await?;
+            let mut removed = false;
+            let content_filter =
+                update_filter(command_interaction, bot_context, |content_filter| {
+                    removed = content_filter.remove(&entry);
+                })
+                .await?;
+            if removed {
+                Ok(content_filter)
+            } else {
+                Err(Error::NotInFilter)
+            }
+        }
+        .await;
+        respond(command_interaction, context, content_filter).await;
+    }
+}
+
+command_options! {
+    /// Options of the `reactinator filter defaults` subcommand.
+    struct DefaultsOptions {
+        /// Refuse the built-in list of slurs and emojis too.
+        enabled(OPTION_ENABLED): bool,
+    }
+}
+
+/// `reactinator filter defaults` subcommand.
+pub struct Defaults;
+
+#[async_trait]
+impl Subcommand for Defaults {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("defaults")
+            .description("Turn the built-in list of words and emojis on or off.");
+        DefaultsOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let content_filter = async {
+            let options = DefaultsOptions::parse(options)?;
+            update_filter(command_interaction, bot_context, |content_filter| {
+                content_filter.use_defaults = options.enabled;
+            })
+            .await
+        }
+        .await;
+        respond(command_interaction, context, content_filter).await;
+    }
+}
+
+/// Apply `update` to the content filter of the guild, returning the
+/// updated filter.
+async fn update_filter(
+    command_interaction: &ApplicationCommandInteraction,
+    bot_context: &BotContext,
+    update: impl FnOnce(&mut ContentFilter),
+) -> Result<ContentFilter, Error> {
+    let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+
+    let content_filter = bot_context.update_content_filter(guild_id, update).await;
+
+    tracing::info!(
+        target: "filter",
+        "user `{}` - updated content filter of guild `{}`",
+        command_interaction.user.tag(),
+        guild_id,
+    );
+    Ok(content_filter)
+}
+
+/// Respond with the content filter or the error.
+async fn respond(
+    command_interaction: &ApplicationCommandInteraction,
+    context: &serenity::prelude::Context,
+    content_filter: Result<ContentFilter, Error>,
+) {
+    let response_content = match content_filter {
+        Ok(content_filter) => content_filter.to_string(),
+        Err(err) => {
+            tracing::error!(
+                target: "filter",
+                "user `{}` - {}",
+                command_interaction.user.tag(),
+                err,
+            );
+            format!("error: {}", err)
+        }
+    };
+
+    if let Err(err) = command_interaction
+        .create_interaction_response(&context.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message.content(response_content).ephemeral(true)
+                })
+        })
+        .await
+    {
+        tracing::error!(
+            "couldn't respond to slash command for user `{}` due to `{}`",
+            command_interaction.user.tag(),
+            err,
+        );
+    }
+}
+
+/// `reactinator filter` related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Used outside of a guild.
+    NotInGuild,
+    /// Entry is neither a word nor an emoji.
+    InvalidEntry,
+    /// Entry to remove is not in the filter.
+    NotInFilter,
+    /// Options are missing or invalid.
+    Options(OptionError),
+}
+
+impl From<OptionError> for Error {
+    fn from(err: OptionError) -> Self {
+        Error::Options(err)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reactinator filter: ")?;
+        match self {
+            Error::NotInGuild => write!(f, "can only be used in a server"),
+            Error::InvalidEntry => write!(f, "entry must be a word or an emoji"),
+            Error::NotInFilter => write!(f, "entry is not in the filter"),
+            Error::Options(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
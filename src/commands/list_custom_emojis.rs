@@ -26,20 +26,20 @@ impl Command for ListCustomEmojis {
     }
 
     async fn interaction(
-        &mut self,
+        &self,
         command_interaction: &ApplicationCommandInteraction,
         context: &serenity::prelude::Context,
         bot_context: &BotContext,
     ) {
-        let guild_emojis = bot_context.guild_emojis.read().await;
+        let emoji_index = bot_context.emoji_index.read().await;
         let emojis = command_interaction
             .guild_id
-            .as_ref()
-            .and_then(|guild_id| guild_emojis.get(guild_id))
+            .and_then(|guild_id| emoji_index.guild(guild_id))
+            .filter(|guild_emojis| !guild_emojis.is_empty())
             .map(|guild_emojis| {
                 let lines = guild_emojis
                     .iter()
-                    .map(|(emoji_name, emoji)| format!("{} - `:{}:`", emoji, emoji_name))
+                    .map(|emoji| format!("{} - `:{}:`", emoji, emoji.name))
                     .collect::<Vec<_>>();
 
                 let mut joined_lines = vec![String::new()];
@@ -0,0 +1,211 @@
+//! Command to view and change which messages can be picked as the
+//! last message of a channel.
+
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    json::Value,
+    model::{
+        application::interaction::{
+            application_command::ApplicationCommandInteraction, InteractionResponseType,
+        },
+        prelude::{command::CommandOptionType, Permissions, UserId},
+    },
+};
+
+use crate::{guild_settings::GuildSettings, BotContext};
+
+use super::Command;
+
+/// `message_settings` command.
+pub struct MessageSettings;
+
+/// Option `ignore_bots`.
+const OPTION_IGNORE_BOTS: &str = "ignore_bots";
+
+/// Option `ignore_webhooks`.
+const OPTION_IGNORE_WEBHOOKS: &str = "ignore_webhooks";
+
+/// Option `ignore_system_messages`.
+const OPTION_IGNORE_SYSTEM_MESSAGES: &str = "ignore_system_messages";
+
+/// Option `allow`.
+const OPTION_ALLOW: &str = "allow";
+
+/// Option `disallow`.
+const OPTION_DISALLOW: &str = "disallow";
+
+#[async_trait]
+impl Command for MessageSettings {
+    fn register(command: &mut CreateApplicationCommand, _bot_context: &BotContext) -> Self {
+        command
+            .name("message_settings")
+            .description("View or change which messages can be picked as the last message.")
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .dm_permission(false)
+            .create_option(|command_option| {
+                command_option
+                    .kind(CommandOptionType::Boolean)
+                    .name(OPTION_IGNORE_BOTS)
+                    .description("Skip the messages of bots.")
+            })
+            .create_option(|command_option| {
+                command_option
+                    .kind(CommandOptionType::Boolean)
+                    .name(OPTION_IGNORE_WEBHOOKS)
+                    .description("Skip the messages sent by webhooks.")
+            })
+            .create_option(|command_option| {
+                command_option
+                    .kind(CommandOptionType::Boolean)
+                    .name(OPTION_IGNORE_SYSTEM_MESSAGES)
+                    .description("Skip system messages such as join notices and pins.")
+            })
+            .create_option(|command_option| {
+                command_option
+                    .kind(CommandOptionType::String)
+                    .name(OPTION_ALLOW)
+                    .description("ID of a bot or webhook whose messages are never skipped.")
+            })
+            .create_option(|command_option| {
+                command_option
+                    .kind(CommandOptionType::String)
+                    .name(OPTION_DISALLOW)
+                    .description("ID of a bot or webhook to remove from the allowed ones.")
+            });
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let response_content = match update_settings(command_interaction, bot_context).await {
+            Ok(guild_settings) => {
+                tracing::info!(
+                    target: "message_settings",
+                    "user `{}` - settings of guild `{:?}` are now {:?}",
+                    command_interaction.user.tag(),
+                    command_interaction.guild_id,
+                    guild_settings,
+                );
+                guild_settings.to_string()
+            }
+            Err(err) => {
+                tracing::error!(
+                    target: "message_settings",
+                    "user `{}` - {}",
+                    command_interaction.user.tag(),
+                    err,
+                );
+                format!("error: {}", err)
+            }
+        };
+
+        if let Err(err) = command_interaction
+            .create_interaction_response(&context.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message.content(response_content).ephemeral(true)
+                    })
+            })
+            .await
+        {
+            tracing::error!(
+                "couldn't respond to slash command for user `{}` due to `{}`",
+                command_interaction.user.tag(),
+                err,
+            );
+        }
+    }
+}
+
+/// Apply the options of the interaction to the settings of the guild,
+/// returning the updated settings.
+async fn update_settings(
+    command_interaction: &ApplicationCommandInteraction,
+    bot_context: &BotContext,
+) -> Result<GuildSettings, Error> {
+    let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+
+    let option_value = |name: &str| {
+        command_interaction
+            .data
+            .options
+            .iter()
+            .find_map(|option| (option.name == name).then_some(option.value.as_ref()))
+            .flatten()
+    };
+    let bool_option = |name: &'static str| match option_value(name) {
+        Some(value) => value
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| Error::OptionMustBeBoolean(name, value.clone())),
+        None => Ok(None),
+    };
+    let author_option = |name: &'static str| match option_value(name) {
+        Some(value) => value
+            .as_str()
+            .and_then(|author_id| author_id.trim().parse::<u64>().ok())
+            .map(|author_id| Some(UserId(author_id)))
+            .ok_or_else(|| Error::InvalidAuthorId(name, value.clone())),
+        None => Ok(None),
+    };
+
+    let ignore_bots = bool_option(OPTION_IGNORE_BOTS)?;
+    let ignore_webhooks = bool_option(OPTION_IGNORE_WEBHOOKS)?;
+    let ignore_system_messages = bool_option(OPTION_IGNORE_SYSTEM_MESSAGES)?;
+    let allow = author_option(OPTION_ALLOW)?;
+    let disallow = author_option(OPTION_DISALLOW)?;
+
+    Ok(bot_context
+        .update_guild_settings(guild_id, |guild_settings| {
+            if let Some(ignore_bots) = ignore_bots {
+                guild_settings.ignore_bots = ignore_bots;
+            }
+            if let Some(ignore_webhooks) = ignore_webhooks {
+                guild_settings.ignore_webhooks = ignore_webhooks;
+            }
+            if let Some(ignore_system_messages) = ignore_system_messages {
+                guild_settings.ignore_system_messages = ignore_system_messages;
+            }
+            if let Some(allow) = allow {
+                guild_settings.allowed_authors.insert(allow);
+            }
+            if let Some(disallow) = disallow {
+                guild_settings.allowed_authors.remove(&disallow);
+            }
+        })
+        .await)
+}
+
+/// `message_settings` related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Used outside of a guild.
+    NotInGuild,
+    /// Option is not a boolean.
+    OptionMustBeBoolean(&'static str, Value),
+    /// Option is not the ID of a bot or webhook.
+    InvalidAuthorId(&'static str, Value),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "message_settings: ")?;
+        match self {
+            Error::NotInGuild => write!(f, "can only be used in a server"),
+            Error::OptionMustBeBoolean(name, value) => {
+                write!(f, "{} must be a boolean, got `{}`", name, value)
+            }
+            Error::InvalidAuthorId(name, value) => {
+                write!(f, "{} must be a bot or webhook id, got `{}`", name, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
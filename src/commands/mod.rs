@@ -1,53 +1,213 @@
 //! Commands.
 
 pub mod add_reaction;
+pub mod command_toggles;
+pub mod config;
+pub mod emoji;
+pub mod filter;
 pub mod list_custom_emojis;
+pub mod message_settings;
+pub mod options;
 pub mod ping;
+pub mod policy;
+pub mod react_with;
+pub mod reactinator;
+pub mod reaction_history;
+pub mod schema;
+pub mod spell_on_message;
+pub mod subcommands;
+pub mod target_options;
 pub mod text_to_reactions;
 
-use std::{borrow::Cow, collections::HashSet, sync::Arc};
+use std::{borrow::Cow, collections::HashSet};
 
 use serenity::{
     async_trait,
     builder::CreateApplicationCommand,
+    http::HttpError,
     model::{
-        application::interaction::application_command::ApplicationCommandInteraction,
+        application::{
+            component::{ActionRowComponent, InputTextStyle},
+            interaction::{
+                application_command::ApplicationCommandInteraction,
+                autocomplete::AutocompleteInteraction, modal::ModalSubmitInteraction,
+                InteractionResponseType,
+            },
+        },
         prelude::{application_command::CommandDataOption, *},
     },
     prelude::*,
 };
 
-use crate::{context::BotAddedReactions, BotContext};
+use crate::{
+    audit_log::{AuditEntry, AuditOutcome},
+    content_filter::Blocked,
+    emojis::{is_external_emoji, resolve_emoji},
+    message_target::MessageTarget,
+    permissions::{
+        MissingPermissions, ReactionPermissions, EXTERNAL_EMOJI_PERMISSIONS, REACT_PERMISSIONS,
+    },
+    rate_limit::RateLimited,
+    reaction_tracker::BotAddedReactions,
+    BotContext,
+};
+
+/// User asking the bot to react through a command.
+#[derive(Debug, Clone, Copy)]
+pub struct Requester<'a> {
+    /// User the bot reacts on behalf of.
+    pub user: &'a User,
+    /// `user` in the guild of the channel, [`None`] outside of guilds.
+    pub member: Option<&'a Member>,
+    /// Command as the user entered it.
+    pub command: &'a str,
+}
 
-/// React to the given message ID with the given emoji text.
+/// React to the given message ID in the given channel with the given
+/// emoji text on behalf of the [`Requester`]. Requests in guilds are
+/// recorded in the [`AuditLog`](crate::audit_log::AuditLog).
 ///
-/// The emoji text must be separated by whitespace.
+/// The emoji text must be separated by whitespace. Emojis of the form
+/// `:name:` are resolved with [`resolve_emoji()`]. Every emoji is
+/// attempted unless the bot or the user lacks the permissions to react
+/// with it, the outcome of each is returned in the
+/// [`ReactToMessageWithReport`]. None are attempted if the
+/// [`ContentFilter`](crate::content_filter::ContentFilter) of the guild
+/// blocks them or the user is rate limited.
 pub async fn react_to_message_with(
+    channel_id: ChannelId,
     message_id: MessageId,
     emoji_text: &str,
-    command_interaction: &ApplicationCommandInteraction,
+    requester: Requester<'_>,
+    context: &Context,
+    bot_context: &BotContext,
+) -> ReactToMessageWithReport {
+    let report = add_reactions(
+        channel_id,
+        message_id,
+        emoji_text,
+        requester.user,
+        requester.member,
+        context,
+        bot_context,
+    )
+    .await;
+
+    if let Some(member) = requester.member {
+        bot_context
+            .record_audit_entry(AuditEntry {
+                timestamp: Timestamp::now(),
+                guild_id: member.guild_id,
+                channel_id,
+                message_id,
+                user_id: requester.user.id,
+                user_tag: requester.user.tag(),
+                command: requester.command.to_string(),
+                emojis: emoji_text.to_string(),
+                outcome: AuditOutcome::from(&report),
+            })
+            .await;
+    }
+
+    report
+}
+
+/// Add the reactions of [`react_to_message_with()`].
+async fn add_reactions(
+    channel_id: ChannelId,
+    message_id: MessageId,
+    emoji_text: &str,
+    user: &User,
+    member: Option<&Member>,
     context: &Context,
     bot_context: &BotContext,
-) -> Result<(), ReactToMessageWithError> {
-    const REACTION_TIMEOUT_TIME_IN_SECONDS: u64 = 10;
+) -> ReactToMessageWithReport {
+    let mut report = ReactToMessageWithReport::default();
 
-    let mut react_to_message_with_err = None;
+    let guild_id = member.map(|member| member.guild_id);
+    // Without the channel or the members in the cache, Discord decides.
+    let permissions =
+        member.and_then(|member| ReactionPermissions::compute(&context.cache, channel_id, member));
 
-    let mut reaction_types = HashSet::new();
-    for emoji in emoji_text
-        .split_whitespace()
-        .map(|emoji| emoji.trim())
-        .filter(|emoji| !emoji.is_empty())
+    if let Some(guild_id) = guild_id {
+        if permissions.is_none_or(|permissions| permissions.check(REACT_PERMISSIONS).is_ok()) {
+            bot_context
+                .ensure_thread_joined(context, guild_id, channel_id)
+                .await;
+        }
+    }
+
+    let emojis = {
+        let emoji_index = bot_context.emoji_index.read().await;
+        emoji_text
+            .split_whitespace()
+            .map(|emoji| emoji.trim())
+            .filter(|emoji| !emoji.is_empty())
+            .map(|emoji| {
+                let resolved_emoji = resolve_emoji(emoji, guild_id, &emoji_index);
+                let reaction_type = match ReactionType::try_from(resolved_emoji.as_str()) {
+                    Ok(reaction_type) => {
+                        let required = if is_external_emoji(&reaction_type, guild_id, &emoji_index)
+                        {
+                            REACT_PERMISSIONS | EXTERNAL_EMOJI_PERMISSIONS
+                        } else {
+                            REACT_PERMISSIONS
+                        };
+                        match permissions.map(|permissions| permissions.check(required)) {
+                            Some(Err(missing)) => Err(ReactionOutcome::MissingPermissions(missing)),
+                            _ => Ok(reaction_type),
+                        }
+                    }
+                    Err(err) => Err(ReactionOutcome::InvalidEmoji(err)),
+                };
+                (emoji, reaction_type)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let reaction_types_to_add = emojis
+        .iter()
+        .filter_map(|(_, reaction_type)| reaction_type.as_ref().ok().cloned())
+        .collect::<Vec<_>>();
+    if let Err(blocked) = bot_context
+        .get_content_filter(guild_id)
+        .await
+        .check_reactions(&reaction_types_to_add)
     {
-        match ReactionType::try_from(emoji) {
+        tracing::info!(
+            "refused reactions to `{}` for user `{}` due to `{}`",
+            message_id,
+            user.tag(),
+            blocked,
+        );
+        report.blocked = Some(blocked);
+        return report;
+    }
+
+    let count = reaction_types_to_add.len();
+    if let Err(err) = bot_context.rate_limiter.lock().await.check_reactions(
+        user.id,
+        count,
+        std::time::Instant::now(),
+    ) {
+        tracing::info!(
+            "refused {} reactions to `{}` for user `{}` due to `{}`",
+            count,
+            message_id,
+            user.tag(),
+            err,
+        );
+        report.rate_limited = Some(err);
+        return report;
+    }
+
+    let mut reaction_types = HashSet::new();
+    for (emoji, reaction_type) in emojis {
+        let outcome = match reaction_type {
             Ok(reaction_type) => {
                 match context
                     .http
-                    .create_reaction(
-                        command_interaction.channel_id.0,
-                        message_id.0,
-                        &reaction_type,
-                    )
+                    .create_reaction(channel_id.0, message_id.0, &reaction_type)
                     .await
                 {
                     Ok(_) => {
@@ -55,172 +215,208 @@ pub async fn react_to_message_with(
                             "added reaction `{}` to `{}` for user `{}`",
                             reaction_type,
                             message_id,
-                            command_interaction.user.tag(),
+                            user.tag(),
                         );
 
-                        reaction_types.insert(reaction_type);
+                        reaction_types.insert(reaction_type.clone());
+                        ReactionOutcome::Added(reaction_type)
                     }
                     Err(err) => {
-                        react_to_message_with_err =
-                            Some(ReactToMessageWithError::CouldNotReactToMessage(err));
+                        tracing::error!(
+                            "couldn't add reaction `{}` to `{}` for user `{}` due to `{}`",
+                            reaction_type,
+                            message_id,
+                            user.tag(),
+                            err,
+                        );
+
+                        ReactionOutcome::from(err)
                     }
                 }
             }
-            Err(err) => {
-                react_to_message_with_err = Some(ReactToMessageWithError::InvalidEmoji(err));
-            }
-        }
+            Err(outcome) => outcome,
+        };
+
+        report.outcomes.push((emoji.to_string(), outcome));
     }
 
     if !reaction_types.is_empty() {
-        if let Some(guild_id) = command_interaction.guild_id {
-            let bot_added_reactions = Arc::new(std::sync::RwLock::new(BotAddedReactions {
-                channel_id: command_interaction.channel_id,
-                message_id,
-                user_id: command_interaction.user.id,
-                reaction_types,
-                creation_time: std::time::Instant::now(),
-            }));
-            bot_context
-                .bot_added_reactions
-                .write()
-                .await
-                .entry(guild_id)
-                .or_insert_with(Vec::new)
-                .push(bot_added_reactions.clone());
-
-            let context_http = context.http.clone();
-            let bot_id = context.cache.current_user_id();
-            let user_tag = command_interaction.user.tag();
-            let command_interaction_user = command_interaction.user.clone();
-            tokio::spawn(async move {
-                tokio::time::sleep(std::time::Duration::from_secs(
-                    REACTION_TIMEOUT_TIME_IN_SECONDS,
-                ))
-                .await;
-
-                let bot_added_reactions = { bot_added_reactions.write().unwrap().clone() };
-                if bot_added_reactions.reaction_types.is_empty() {
-                    tracing::info!(
-                        "user `{}` has reacted to all \
-                         reactions for message `{}` in channel `{}`",
-                        user_tag,
-                        bot_added_reactions.message_id,
-                        bot_added_reactions.channel_id
-                    );
-                } else {
-                    tracing::info!(
-                        "attempting to remove reactions because \
-                         user `{}` didn't interact with them",
-                        user_tag,
-                    );
-                    for reaction_type in &bot_added_reactions.reaction_types {
-                        match context_http
-                            .delete_reaction(
-                                bot_added_reactions.channel_id.0,
-                                bot_added_reactions.message_id.0,
-                                Some(bot_id.0),
-                                reaction_type,
-                            )
-                            .await
-                        {
-                            Ok(_) => {
-                                tracing::info!(
-                                    "deleted `{}` reaction from message\
-                                     `{}` in channel `{}` succesfully \
-                                     because user `{}` didn't react",
-                                    reaction_type,
-                                    bot_added_reactions.message_id,
-                                    bot_added_reactions.channel_id,
-                                    user_tag,
-                                );
-                            }
-                            Err(err) => {
-                                tracing::error!(
-                                    "couldn't delete `{}` reaction from \
-                                     message `{}` in channel `{}` because \
-                                     user `{}` didn't react due to `{}",
-                                    reaction_type,
-                                    bot_added_reactions.message_id,
-                                    bot_added_reactions.channel_id,
-                                    user_tag,
-                                    err,
-                                );
-                            }
-                        }
-                    }
-                    match command_interaction_user
-                        .direct_message(&context_http, |create_message| {
-                            create_message.content(format!(
-                                "Removed reactions \"{}\" for message `{}` since \
-                                 you did **not** react within {} seconds.",
-                                bot_added_reactions
-                                    .reaction_types
-                                    .iter()
-                                    .map(|reaction| reaction.to_string())
-                                    .collect::<Vec<_>>()
-                                    .join(", "),
-                                bot_added_reactions.message_id,
-                                REACTION_TIMEOUT_TIME_IN_SECONDS,
-                            ))
-                        })
-                        .await
-                    {
-                        Ok(_) => {
-                            tracing::info!(
-                                "informed `{}` about deleting the \
-                                 reactions from message `{}` in channel `{}`",
-                                user_tag,
-                                bot_added_reactions.message_id,
-                                bot_added_reactions.channel_id
-                            );
-                        }
-                        Err(err) => {
-                            tracing::error!(
-                                "couldn't inform `{}` about deleting \
-                                 the reactions from message `{}` \
-                                 in channel `{}` due to `{}`",
-                                user_tag,
-                                bot_added_reactions.message_id,
-                                bot_added_reactions.channel_id,
-                                err
-                            );
-                        }
-                    }
-                }
-            })
-            .await
-            .unwrap();
+        if let Some(guild_id) = guild_id {
+            let guild_config = bot_context.get_guild_config(Some(guild_id)).await;
+            bot_context.reaction_tracker.track(
+                context,
+                user,
+                BotAddedReactions {
+                    guild_id,
+                    channel_id,
+                    message_id,
+                    user_id: user.id,
+                    reaction_types,
+                },
+                guild_config,
+            );
         }
     }
 
-    if let Some(err) = react_to_message_with_err {
-        Err(err)
-    } else {
-        Ok(())
-    }
+    report
 }
 
-/// [`react_to_message_with()`] errors.
+/// Outcome of reacting with a single emoji of
+/// [`react_to_message_with()`].
 #[derive(Debug)]
-pub enum ReactToMessageWithError {
-    CouldNotReactToMessage(serenity::Error),
+pub enum ReactionOutcome {
+    /// Reaction was added.
+    Added(ReactionType),
+    /// Emoji could not be converted to a [`ReactionType`].
     InvalidEmoji(ReactionConversionError),
+    /// Bot or user lacks the permissions to react with the emoji,
+    /// found before reacting.
+    MissingPermissions(MissingPermissions),
+    /// Bot is not permitted to react to the message.
+    PermissionDenied,
+    /// Emoji is not known to Discord or not usable by the bot.
+    UnknownEmoji,
+    /// Message has reached the maximum number of reactions.
+    ReactionLimitReached,
+    /// Discord rate limited the reaction.
+    RateLimited,
+    /// Reacting failed for some other reason.
+    Failed(serenity::Error),
+}
+
+impl ReactionOutcome {
+    /// Discord JSON error code for an unknown emoji.
+    const DISCORD_UNKNOWN_EMOJI: isize = 10014;
+    /// Discord JSON error code for maximum number of reactions
+    /// reached.
+    const DISCORD_MAX_REACTIONS: isize = 30010;
+    /// Discord JSON error code for missing permissions.
+    const DISCORD_MISSING_PERMISSIONS: isize = 50013;
+
+    /// Check if the reaction was added.
+    pub fn is_added(&self) -> bool {
+        matches!(self, Self::Added(_))
+    }
 }
 
-impl std::fmt::Display for ReactToMessageWithError {
+impl From<serenity::Error> for ReactionOutcome {
+    fn from(err: serenity::Error) -> Self {
+        if let serenity::Error::Http(http_err) = &err {
+            if let HttpError::UnsuccessfulRequest(response) = http_err.as_ref() {
+                match response.error.code {
+                    Self::DISCORD_UNKNOWN_EMOJI => return Self::UnknownEmoji,
+                    Self::DISCORD_MAX_REACTIONS => return Self::ReactionLimitReached,
+                    Self::DISCORD_MISSING_PERMISSIONS => return Self::PermissionDenied,
+                    _ => {}
+                }
+                match response.status_code.as_u16() {
+                    403 => return Self::PermissionDenied,
+                    429 => return Self::RateLimited,
+                    _ => {}
+                }
+            }
+        }
+        Self::Failed(err)
+    }
+}
+
+impl std::fmt::Display for ReactionOutcome {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "react_to_message_with: ")?;
         match self {
-            Self::InvalidEmoji(err) => write!(f, "invalid emoji: `{}`", err),
-            Self::CouldNotReactToMessage(err) => write!(f, "could not react to message: {}", err),
+            Self::Added(_) => write!(f, "added"),
+            Self::InvalidEmoji(err) => write!(f, "invalid emoji: {}", err),
+            Self::MissingPermissions(missing) => write!(f, "{}", missing),
+            Self::PermissionDenied => write!(f, "permission denied"),
+            Self::UnknownEmoji => write!(f, "unknown emoji"),
+            Self::ReactionLimitReached => write!(f, "reaction limit reached"),
+            Self::RateLimited => write!(f, "rate limited"),
+            Self::Failed(err) => write!(f, "failed: {}", err),
+        }
+    }
+}
+
+/// Report of [`react_to_message_with()`].
+#[derive(Debug, Default)]
+pub struct ReactToMessageWithReport {
+    /// Emoji text to its [`ReactionOutcome`], in the order they were
+    /// given.
+    pub outcomes: Vec<(String, ReactionOutcome)>,
+    /// Content filter that refused all the reactions.
+    pub blocked: Option<Blocked>,
+    /// Rate limit that refused all the reactions.
+    pub rate_limited: Option<RateLimited>,
+}
+
+impl ReactToMessageWithReport {
+    /// Reactions that were added.
+    pub fn added(&self) -> impl Iterator<Item = &ReactionType> {
+        self.outcomes
+            .iter()
+            .filter_map(|(_, outcome)| match outcome {
+                ReactionOutcome::Added(reaction_type) => Some(reaction_type),
+                _ => None,
+            })
+    }
+
+    /// Check if any of the reactions failed.
+    pub fn has_failures(&self) -> bool {
+        self.blocked.is_some()
+            || self.rate_limited.is_some()
+            || self.outcomes.iter().any(|(_, outcome)| !outcome.is_added())
+    }
+
+    /// Response content for the user who requested the reactions to
+    /// the given message.
+    pub fn response_content(&self, message_id: MessageId) -> String {
+        let added = self
+            .added()
+            .map(|reaction_type| reaction_type.to_string())
+            .collect::<Vec<_>>();
+
+        if added.is_empty() {
+            format!("Couldn't react to message `{}`.\n{}", message_id, self)
+        } else if self.has_failures() {
+            format!(
+                "Don't forget to react to message `{}` yourself for the reactions {}.\n{}",
+                message_id,
+                added.join(" "),
+                self
+            )
+        } else {
+            format!(
+                "Don't forget to react to message `{}` yourself for the reactions {}.",
+                message_id,
+                added.join(" "),
+            )
         }
     }
 }
 
-impl std::error::Error for ReactToMessageWithError {}
+impl std::fmt::Display for ReactToMessageWithReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(blocked) = &self.blocked {
+            writeln!(f, "\u{1f6ab} {}", blocked)?;
+        }
+        if let Some(rate_limited) = &self.rate_limited {
+            writeln!(f, "\u{23f3} {}", rate_limited)?;
+        }
+        for (emoji, outcome) in &self.outcomes {
+            match outcome {
+                ReactionOutcome::Added(reaction_type) => {
+                    writeln!(f, "\u{2705} {} - {}", reaction_type, outcome)?
+                }
+                _ => writeln!(f, "\u{274c} `{}` - {}", emoji, outcome)?,
+            }
+        }
+        Ok(())
+    }
+}
 
 /// Discord command.
+///
+/// Interactions with the commands are handled concurrently through
+/// shared references, any state of a command must be behind its own
+/// synchronisation.
 #[async_trait]
 pub trait Command: Send + Sync + 'static {
     /// Register the command.
@@ -230,11 +426,219 @@ pub trait Command: Send + Sync + 'static {
 
     /// Interaction with the command.
     async fn interaction(
-        &mut self,
+        &self,
         command_interaction: &ApplicationCommandInteraction,
         context: &Context,
         bot_context: &BotContext,
     );
+
+    /// Autocompletion of an option of the command.
+    async fn autocomplete(
+        &self,
+        _autocomplete_interaction: &AutocompleteInteraction,
+        _context: &Context,
+        _bot_context: &BotContext,
+    ) {
+    }
+
+    /// Submission of a modal opened by the command.
+    ///
+    /// The modal's custom ID must be created with
+    /// [`modal_custom_id()`] for the submission to be routed to the
+    /// command.
+    async fn modal_submit(
+        &self,
+        _modal_interaction: &ModalSubmitInteraction,
+        _context: &Context,
+        _bot_context: &BotContext,
+    ) {
+    }
+}
+
+/// Respond to the command with the message to react to and the
+/// emojis to react with, or with the error. Then react and edit the
+/// response with the [`ReactToMessageWithReport`].
+pub async fn respond_and_react<E: std::fmt::Display>(
+    command_interaction: &ApplicationCommandInteraction,
+    context: &Context,
+    bot_context: &BotContext,
+    reaction: Result<((ChannelId, MessageId), String), E>,
+) {
+    if let Err(err) = &reaction {
+        tracing::error!("user `{}` - {}", command_interaction.user.tag(), err);
+    }
+
+    if let Err(err) = command_interaction
+        .create_interaction_response(&context.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message
+                        .content(match &reaction {
+                            Ok(((_, message_id), emojis)) => {
+                                format!("Reacting to message `{}` with {}.", message_id, emojis)
+                            }
+                            Err(err) => format!("error: {}", err),
+                        })
+                        .ephemeral(true)
+                })
+        })
+        .await
+    {
+        tracing::error!(
+            "couldn't respond to slash command for user `{}` due to `{}`",
+            command_interaction.user.tag(),
+            err,
+        );
+    }
+
+    if let Ok(((channel_id, message_id), emojis)) = reaction {
+        let report = react_to_message_with(
+            channel_id,
+            message_id,
+            &emojis,
+            Requester {
+                user: &command_interaction.user,
+                member: command_interaction.member.as_ref(),
+                command: &command_interaction_to_string(command_interaction),
+            },
+            context,
+            bot_context,
+        )
+        .await;
+
+        if report.has_failures() {
+            tracing::error!(
+                "user `{}` - couldn't add all reactions to `{}`:\n{}",
+                command_interaction.user.tag(),
+                message_id,
+                report,
+            );
+        }
+
+        if let Err(err) = command_interaction
+            .edit_original_interaction_response(&context.http, |response| {
+                response.content(report.response_content(message_id))
+            })
+            .await
+        {
+            tracing::error!(
+                "couldn't edit interaction response message to \
+                 slash command for user `{}` due to `{}`",
+                command_interaction.user.tag(),
+                err,
+            );
+        }
+    }
+}
+
+/// Custom ID of a modal opened by the command `command_name` with
+/// the command specific `data`.
+pub fn modal_custom_id(command_name: &str, data: impl std::fmt::Display) -> String {
+    format!("{}:{}", command_name, data)
+}
+
+/// Split the custom ID created by [`modal_custom_id()`] into the
+/// command name and the command specific data.
+pub fn split_modal_custom_id(custom_id: &str) -> Option<(&str, &str)> {
+    custom_id.split_once(':')
+}
+
+/// Open a modal asking for a single line of text for the message
+/// targeted by the message command `command_name`.
+///
+/// The modal's custom ID carries the targeted message, see
+/// [`modal_message_target()`].
+pub async fn open_message_command_modal(
+    command_name: &str,
+    title: &str,
+    input_custom_id: &str,
+    input_label: &str,
+    command_interaction: &ApplicationCommandInteraction,
+    context: &Context,
+) {
+    let message_id = match command_interaction.data.target_id {
+        Some(target_id) => target_id.to_message_id(),
+        None => {
+            tracing::error!(
+                "message command `{}` invoked without a target message",
+                command_name
+            );
+            return;
+        }
+    };
+
+    if let Err(err) = command_interaction
+        .create_interaction_response(&context.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|modal| {
+                    modal
+                        .custom_id(modal_custom_id(
+                            command_name,
+                            format!("{}-{}", command_interaction.channel_id, message_id),
+                        ))
+                        .title(title)
+                        .components(|components| {
+                            components.create_action_row(|action_row| {
+                                action_row.create_input_text(|input_text| {
+                                    input_text
+                                        .custom_id(input_custom_id)
+                                        .label(input_label)
+                                        .style(InputTextStyle::Short)
+                                        .required(true)
+                                })
+                            })
+                        })
+                })
+        })
+        .await
+    {
+        tracing::error!(
+            "couldn't open modal for `{}` for user `{}` due to `{}`",
+            command_name,
+            command_interaction.user.tag(),
+            err,
+        );
+    }
+}
+
+/// Message targeted by the modal opened by
+/// [`open_message_command_modal()`].
+pub fn modal_message_target(
+    modal_interaction: &ModalSubmitInteraction,
+) -> Option<(ChannelId, MessageId)> {
+    let (_, data) = split_modal_custom_id(&modal_interaction.data.custom_id)?;
+    match data.parse::<MessageTarget>().ok()? {
+        MessageTarget::Id {
+            channel_id,
+            message_id,
+            ..
+        } => Some((
+            channel_id.unwrap_or(modal_interaction.channel_id),
+            message_id,
+        )),
+        MessageTarget::Relative(_) | MessageTarget::ThreadStarter => None,
+    }
+}
+
+/// Value of the input text with the given custom ID of the
+/// [`ModalSubmitInteraction`].
+pub fn modal_input_text<'a>(
+    modal_interaction: &'a ModalSubmitInteraction,
+    custom_id: &str,
+) -> Option<&'a str> {
+    modal_interaction
+        .data
+        .components
+        .iter()
+        .flat_map(|action_row| &action_row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input_text) if input_text.custom_id == custom_id => {
+                Some(input_text.value.as_str())
+            }
+            _ => None,
+        })
 }
 
 /// Convert the [`ApplicationCommandInteraction`] to a string.
@@ -285,3 +689,90 @@ pub fn command_data_option_to_string(command_data_option: &CommandDataOption) ->
             .join(" ")
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use serenity::{
+        http::{error::ErrorResponse, HttpError, StatusCode},
+        json::json,
+        model::prelude::*,
+    };
+
+    use super::{ReactToMessageWithReport, ReactionOutcome};
+
+    /// Error of a reaction request answered with the status and the
+    /// Discord JSON error code.
+    fn http_error(status: u16, code: isize) -> serenity::Error {
+        serenity::Error::Http(Box::new(HttpError::UnsuccessfulRequest(ErrorResponse {
+            status_code: StatusCode::from_u16(status).unwrap(),
+            url: "https://discord.com/api/v10/channels/1/messages/2/reactions/%F0%9F%91%8D/@me"
+                .parse()
+                .unwrap(),
+            error: serde_json::from_value(json!({ "code": code, "message": "error" })).unwrap(),
+        })))
+    }
+
+    /// Discord error codes are mapped first, then the HTTP status.
+    #[test]
+    fn react_to_message_with_report_01() {
+        assert!(matches!(
+            ReactionOutcome::from(http_error(400, 10014)),
+            ReactionOutcome::UnknownEmoji
+        ));
+        assert!(matches!(
+            ReactionOutcome::from(http_error(400, 30010)),
+            ReactionOutcome::ReactionLimitReached
+        ));
+        assert!(matches!(
+            ReactionOutcome::from(http_error(403, 50013)),
+            ReactionOutcome::PermissionDenied
+        ));
+        assert!(matches!(
+            ReactionOutcome::from(http_error(403, 50001)),
+            ReactionOutcome::PermissionDenied
+        ));
+        assert!(matches!(
+            ReactionOutcome::from(http_error(429, 0)),
+            ReactionOutcome::RateLimited
+        ));
+        assert!(matches!(
+            ReactionOutcome::from(http_error(500, 0)),
+            ReactionOutcome::Failed(_)
+        ));
+        assert!(matches!(
+            ReactionOutcome::from(serenity::Error::Other("error")),
+            ReactionOutcome::Failed(_)
+        ));
+    }
+
+    /// Response lists the failures unless every reaction was added.
+    #[test]
+    fn react_to_message_with_report_02() {
+        let thumbs_up = ReactionType::Unicode("👍".to_string());
+        let mut report = ReactToMessageWithReport {
+            outcomes: vec![("👍".to_string(), ReactionOutcome::Added(thumbs_up))],
+            ..Default::default()
+        };
+        assert_eq!(
+            report.response_content(MessageId(2)),
+            "Don't forget to react to message `2` yourself for the reactions 👍."
+        );
+
+        report.outcomes.push((
+            ":nope:".to_string(),
+            ReactionOutcome::from(http_error(400, 10014)),
+        ));
+        assert_eq!(
+            report.response_content(MessageId(2)),
+            "Don't forget to react to message `2` yourself for the reactions 👍.\n\
+             \u{2705} 👍 - added\n\
+             \u{274c} `:nope:` - unknown emoji\n"
+        );
+
+        report.outcomes.remove(0);
+        assert_eq!(
+            report.response_content(MessageId(2)),
+            "Couldn't react to message `2`.\n\u{274c} `:nope:` - unknown emoji\n"
+        );
+    }
+}
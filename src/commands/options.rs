@@ -0,0 +1,388 @@
+//! Typed options of the commands.
+//!
+//! A command declares its options as a struct through
+//! [`command_options!`], which generates both the registration of the
+//! options and their parsing.
+
+use serenity::{
+    builder::CreateApplicationCommandOption,
+    json::Value,
+    model::application::{
+        command::CommandOptionType, interaction::application_command::CommandDataOption,
+    },
+    model::prelude::{ChannelId, RoleId, UserId},
+};
+
+use crate::message_target::{MessageTarget, MessageTargetError};
+
+/// Value of an option of a given [`CommandOptionType`].
+pub trait OptionValue: Sized {
+    /// [`CommandOptionType`] of the option.
+    const KIND: CommandOptionType;
+
+    /// Parse the value given by Discord.
+    fn parse(value: &Value) -> Result<Self, OptionValueError>;
+}
+
+impl OptionValue for String {
+    const KIND: CommandOptionType = CommandOptionType::String;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or(OptionValueError::Expected("a string"))
+    }
+}
+
+impl OptionValue for bool {
+    const KIND: CommandOptionType = CommandOptionType::Boolean;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_bool()
+            .ok_or(OptionValueError::Expected("a boolean"))
+    }
+}
+
+impl OptionValue for i64 {
+    const KIND: CommandOptionType = CommandOptionType::Integer;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_i64()
+            .ok_or(OptionValueError::Expected("an integer"))
+    }
+}
+
+impl OptionValue for usize {
+    const KIND: CommandOptionType = CommandOptionType::Integer;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_u64()
+            .and_then(|value| usize::try_from(value).ok())
+            .ok_or(OptionValueError::Expected("a non negative integer"))
+    }
+}
+
+impl OptionValue for UserId {
+    const KIND: CommandOptionType = CommandOptionType::User;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_str()
+            .and_then(|user_id| user_id.parse::<u64>().ok())
+            .map(UserId)
+            .ok_or(OptionValueError::Expected("a user"))
+    }
+}
+
+impl OptionValue for RoleId {
+    const KIND: CommandOptionType = CommandOptionType::Role;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_str()
+            .and_then(|role_id| role_id.parse::<u64>().ok())
+            .map(RoleId)
+            .ok_or(OptionValueError::Expected("a role"))
+    }
+}
+
+impl OptionValue for ChannelId {
+    const KIND: CommandOptionType = CommandOptionType::Channel;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_str()
+            .and_then(|channel_id| channel_id.parse::<u64>().ok())
+            .map(ChannelId)
+            .ok_or(OptionValueError::Expected("a channel"))
+    }
+}
+
+impl OptionValue for MessageTarget {
+    const KIND: CommandOptionType = CommandOptionType::String;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        value
+            .as_str()
+            .ok_or(OptionValueError::Expected("a string"))?
+            .parse()
+            .map_err(OptionValueError::MessageTarget)
+    }
+}
+
+/// Field of a [`command_options!`] struct, either a required
+/// [`OptionValue`] or an optional one.
+pub trait OptionField: Sized {
+    /// [`OptionValue`] of the field.
+    type Value: OptionValue;
+
+    /// Option must be given.
+    const REQUIRED: bool;
+
+    /// Parse the value of the option `name`, [`None`] if not given.
+    fn from_option(name: &'static str, value: Option<&Value>) -> Result<Self, OptionError>;
+}
+
+impl<T: OptionValue> OptionField for T {
+    type Value = T;
+
+    const REQUIRED: bool = true;
+
+    fn from_option(name: &'static str, value: Option<&Value>) -> Result<Self, OptionError> {
+        Option::<T>::from_option(name, value)?.ok_or(OptionError::Missing(name))
+    }
+}
+
+impl<T: OptionValue> OptionField for Option<T> {
+    type Value = T;
+
+    const REQUIRED: bool = false;
+
+    fn from_option(name: &'static str, value: Option<&Value>) -> Result<Self, OptionError> {
+        value
+            .map(|value| {
+                T::parse(value).map_err(|error| OptionError::Invalid {
+                    name,
+                    value: value.clone(),
+                    error,
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Value of the option `name` given to the command.
+pub fn option_value<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a Value> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_ref())
+}
+
+/// Apply the extra configuration of an option, see
+/// [`command_options!`].
+pub fn configure_option(
+    option: &mut CreateApplicationCommandOption,
+    configure: impl FnOnce(&mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption,
+) {
+    configure(option);
+}
+
+/// Declare the options of a command as a struct.
+///
+/// The doc comment of each field is its description and the
+/// expression in parentheses its name. Fields of type [`Option`] are
+/// optional. An extra configuration of the option can be given after
+/// `=>`.
+///
+/// ```ignore
+/// command_options! {
+///     /// Options of the `add_reaction` command.
+///     pub struct Options {
+///         /// Emoji to react with.
+///         emoji(OPTION_EMOJI): String => |option| option.set_autocomplete(true),
+///         /// Number of messages up to react to.
+///         offset(OPTION_OFFSET): Option<usize>,
+///     }
+/// }
+/// ```
+///
+/// Generates `Options::register()` to add the options to the
+/// command, `Options::register_subcommand()` to add them to a
+/// subcommand instead and `Options::parse()` to parse the options
+/// given to it.
+macro_rules! command_options {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[doc = $description:literal])+
+                $field:ident($option_name:expr): $ty:ty $(=> $configure:expr)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[doc = $description])+
+                pub $field: $ty,
+            )*
+        }
+
+        impl $name {
+            /// Options of the command.
+            fn create_options() -> Vec<serenity::builder::CreateApplicationCommandOption> {
+                vec![$({
+                    let mut option = serenity::builder::CreateApplicationCommandOption::default();
+                    option
+                        .name($option_name)
+                        .description(concat!($($description),+).trim())
+                        .kind(
+                            <<$ty as $crate::commands::options::OptionField>::Value
+                                as $crate::commands::options::OptionValue>::KIND,
+                        )
+                        .required(<$ty as $crate::commands::options::OptionField>::REQUIRED);
+                    $($crate::commands::options::configure_option(&mut option, $configure);)?
+                    option
+                }),*]
+            }
+
+            /// Add the options to the command.
+            #[allow(dead_code)]
+            pub fn register(
+                command: &mut serenity::builder::CreateApplicationCommand,
+            ) -> &mut serenity::builder::CreateApplicationCommand {
+                Self::create_options().into_iter().for_each(|option| {
+                    command.add_option(option);
+                });
+                command
+            }
+
+            /// Add the options to the subcommand.
+            #[allow(dead_code)]
+            pub fn register_subcommand(
+                subcommand: &mut serenity::builder::CreateApplicationCommandOption,
+            ) -> &mut serenity::builder::CreateApplicationCommandOption {
+                Self::create_options().into_iter().for_each(|option| {
+                    subcommand.add_sub_option(option);
+                });
+                subcommand
+            }
+
+            /// Parse the options given to the command.
+            pub fn parse(
+                options: &[serenity::model::application::interaction::application_command::CommandDataOption],
+            ) -> Result<Self, $crate::commands::options::OptionError> {
+                Ok(Self {
+                    $(
+                        $field: <$ty as $crate::commands::options::OptionField>::from_option(
+                            $option_name,
+                            $crate::commands::options::option_value(options, $option_name),
+                        )?,
+                    )*
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use command_options;
+
+/// Errors of a single [`OptionValue`].
+#[derive(Debug)]
+pub enum OptionValueError {
+    /// Value is not of the expected kind.
+    Expected(&'static str),
+    /// Value is not a valid [`MessageTarget`].
+    MessageTarget(MessageTargetError),
+}
+
+impl std::fmt::Display for OptionValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionValueError::Expected(expected) => write!(f, "expected {}", expected),
+            OptionValueError::MessageTarget(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for OptionValueError {}
+
+/// Errors of the options given to a command.
+#[derive(Debug)]
+pub enum OptionError {
+    /// Required option is missing.
+    Missing(&'static str),
+    /// Option has an invalid value.
+    Invalid {
+        name: &'static str,
+        value: Value,
+        error: OptionValueError,
+    },
+}
+
+impl std::fmt::Display for OptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionError::Missing(name) => write!(f, "requires {}", name),
+            OptionError::Invalid { name, value, error } => {
+                write!(f, "invalid {} `{}`: {}", name, value, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
+
+#[cfg(test)]
+mod tests {
+    use serenity::{
+        json::{json, prelude::from_value},
+        model::application::interaction::application_command::CommandDataOption,
+    };
+
+    use super::OptionError;
+    use crate::message_target::MessageTarget;
+
+    command_options! {
+        /// Options for testing.
+        struct TestOptions {
+            /// Text.
+            text("text"): String,
+            /// Offset.
+            offset("offset"): Option<usize> => |option| option.min_int_value(1),
+            /// Target.
+            target("target"): Option<MessageTarget>,
+        }
+    }
+
+    fn options(options: serenity::json::Value) -> Vec<CommandDataOption> {
+        from_value(options).unwrap()
+    }
+
+    /// Options are parsed by name and type, optional ones may be
+    /// missing.
+    #[test]
+    fn command_options_01() {
+        let parsed = TestOptions::parse(&options(json!([
+            {"name": "text", "type": 3, "value": "hello"},
+            {"name": "target", "type": 3, "value": "^2"},
+        ])))
+        .unwrap();
+        assert_eq!(parsed.text, "hello");
+        assert_eq!(parsed.offset, None);
+        assert_eq!(parsed.target, Some(MessageTarget::Relative(2)));
+
+        assert!(matches!(
+            TestOptions::parse(&options(json!([]))),
+            Err(OptionError::Missing("text"))
+        ));
+        assert!(matches!(
+            TestOptions::parse(&options(json!([
+                {"name": "text", "type": 3, "value": "hello"},
+                {"name": "offset", "type": 4, "value": -1},
+            ]))),
+            Err(OptionError::Invalid { name: "offset", .. })
+        ));
+    }
+
+    /// Registration sets the kind, description and extra
+    /// configuration of the options.
+    #[test]
+    fn command_options_02() {
+        let mut command = serenity::builder::CreateApplicationCommand::default();
+        TestOptions::register(&mut command);
+        let options = command.0.get("options").unwrap().as_array().unwrap();
+        assert_eq!(options.len(), 3);
+        assert_eq!(options[0]["name"], "text");
+        assert_eq!(options[0]["description"], "Text.");
+        assert_eq!(options[0]["required"], true);
+        assert_eq!(options[1]["type"], 4);
+        assert_eq!(options[1]["required"], false);
+        assert_eq!(options[1]["min_value"], 1);
+    }
+}
@@ -20,7 +20,7 @@ impl Command for Ping {
     }
 
     async fn interaction(
-        &mut self,
+        &self,
         command_interaction: &serenity::model::prelude::application_command::ApplicationCommandInteraction,
         context: &serenity::prelude::Context,
         _bot_context: &BotContext,
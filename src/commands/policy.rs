@@ -0,0 +1,332 @@
+//! `reactinator policy` subcommands to view and change who can use
+//! the commands and where, see [`GuildPolicy`].
+
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommandOption,
+    json::Value,
+    model::{
+        application::{
+            command::CommandOptionType,
+            interaction::application_command::{ApplicationCommandInteraction, CommandDataOption},
+        },
+        prelude::*,
+    },
+};
+
+use crate::{
+    command_toggles::TOGGLEABLE_COMMANDS,
+    policy::{Access, GuildPolicy},
+    BotContext,
+};
+
+use super::{
+    command_toggles::command_choices,
+    options::{command_options, OptionError, OptionValue, OptionValueError},
+    subcommands::{Subcommand, SubcommandGroup},
+};
+
+/// Register the `policy` subcommands in the group.
+pub fn register(group: &mut SubcommandGroup, bot_context: &BotContext) {
+    group
+        .subcommand::<Show>(bot_context)
+        .subcommand::<Role>(bot_context)
+        .subcommand::<Channel>(bot_context)
+        .subcommand::<Reset>(bot_context);
+}
+
+/// Option `command`.
+const OPTION_COMMAND: &str = "command";
+
+/// Option `role`.
+const OPTION_ROLE: &str = "role";
+
+/// Option `channel`.
+const OPTION_CHANNEL: &str = "channel";
+
+/// Option `access`.
+const OPTION_ACCESS: &str = "access";
+
+/// Access to give, as an option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessOption(Option<Access>);
+
+impl AccessOption {
+    /// Add the choices to the option.
+    fn choices(option: &mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption {
+        option
+            .add_string_choice("allow", "allow")
+            .add_string_choice("deny", "deny")
+            .add_string_choice("clear", "clear")
+    }
+}
+
+impl OptionValue for AccessOption {
+    const KIND: CommandOptionType = CommandOptionType::String;
+
+    fn parse(value: &Value) -> Result<Self, OptionValueError> {
+        match value.as_str() {
+            Some("allow") => Ok(Self(Some(Access::Allow))),
+            Some("deny") => Ok(Self(Some(Access::Deny))),
+            Some("clear") => Ok(Self(None)),
+            _ => Err(OptionValueError::Expected("allow, deny or clear")),
+        }
+    }
+}
+
+/// `reactinator policy show` subcommand.
+pub struct Show;
+
+#[async_trait]
+impl Subcommand for Show {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("show")
+            .description("Show who can use the commands and where.");
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        _options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let guild_policy = async {
+            let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+            Ok(bot_context
+                .guild_policies
+                .read()
+                .await
+                .get(&guild_id)
+                .cloned()
+                .unwrap_or_default())
+        }
+        .await;
+        respond(command_interaction, context, guild_policy).await;
+    }
+}
+
+command_options! {
+    /// Options of the `reactinator policy role` subcommand.
+    struct RoleOptions {
+        /// Command to allow or deny the role.
+        command(OPTION_COMMAND): String => command_choices,
+        /// Role to allow, deny or clear.
+        role(OPTION_ROLE): RoleId,
+        /// Allowing any role denies the roles not allowed.
+        access(OPTION_ACCESS): AccessOption => AccessOption::choices,
+    }
+}
+
+/// `reactinator policy role` subcommand.
+pub struct Role;
+
+#[async_trait]
+impl Subcommand for Role {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("role")
+            .description("Allow or deny a role to use a command.");
+        RoleOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let guild_policy = async {
+            let options = RoleOptions::parse(options)?;
+            // `reactinator` is not offered, the members it is meant for
+            // can manage the guild and so are not subject to the
+            // policy.
+            let command_name = options.command.trim().trim_start_matches('/');
+            if !TOGGLEABLE_COMMANDS
+                .iter()
+                .any(|(name, _)| *name == command_name)
+            {
+                return Err(Error::UnknownCommand(command_name.to_string()));
+            }
+            update_policy(command_interaction, bot_context, |guild_policy| {
+                guild_policy.set_command_role(command_name, options.role, options.access.0);
+            })
+            .await
+        }
+        .await;
+        respond(command_interaction, context, guild_policy).await;
+    }
+}
+
+command_options! {
+    /// Options of the `reactinator policy channel` subcommand.
+    struct ChannelOptions {
+        /// Channel to allow, deny or clear, applies to its threads too.
+        channel(OPTION_CHANNEL): ChannelId,
+        /// Allowing any channel denies the channels not allowed.
+        access(OPTION_ACCESS): AccessOption => AccessOption::choices,
+    }
+}
+
+/// `reactinator policy channel` subcommand.
+pub struct Channel;
+
+#[async_trait]
+impl Subcommand for Channel {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("channel")
+            .description("Allow or deny the commands in a channel.");
+        ChannelOptions::register_subcommand(subcommand);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let guild_policy = async {
+            let options = ChannelOptions::parse(options)?;
+            update_policy(command_interaction, bot_context, |guild_policy| {
+                guild_policy.channels.set(options.channel, options.access.0);
+            })
+            .await
+        }
+        .await;
+        respond(command_interaction, context, guild_policy).await;
+    }
+}
+
+/// `reactinator policy reset` subcommand.
+pub struct Reset;
+
+#[async_trait]
+impl Subcommand for Reset {
+    fn register(
+        subcommand: &mut CreateApplicationCommandOption,
+        _bot_context: &BotContext,
+    ) -> Self {
+        subcommand
+            .name("reset")
+            .description("Allow everyone to use the commands everywhere.");
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        _options: &[CommandDataOption],
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let guild_policy = update_policy(command_interaction, bot_context, |guild_policy| {
+            *guild_policy = GuildPolicy::default();
+        })
+        .await;
+        respond(command_interaction, context, guild_policy).await;
+    }
+}
+
+/// Apply `update` to the policy of the guild, returning the updated
+/// policy.
+async fn update_policy(
+    command_interaction: &ApplicationCommandInteraction,
+    bot_context: &BotContext,
+    update: impl FnOnce(&mut GuildPolicy),
+) -> Result<GuildPolicy, Error> {
+    let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+
+    let guild_policy = bot_context.update_guild_policy(guild_id, update).await;
+
+    tracing::info!(
+        target: "policy",
+        "user `{}` - policy of guild `{}` is now {:?}",
+        command_interaction.user.tag(),
+        guild_id,
+        guild_policy,
+    );
+    Ok(guild_policy)
+}
+
+/// Respond with the policy or the error.
+async fn respond(
+    command_interaction: &ApplicationCommandInteraction,
+    context: &serenity::prelude::Context,
+    guild_policy: Result<GuildPolicy, Error>,
+) {
+    let response_content = match guild_policy {
+        Ok(guild_policy) => guild_policy.to_string(),
+        Err(err) => {
+            tracing::error!(
+                target: "policy",
+                "user `{}` - {}",
+                command_interaction.user.tag(),
+                err,
+            );
+            format!("error: {}", err)
+        }
+    };
+
+    if let Err(err) = command_interaction
+        .create_interaction_response(&context.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message.content(response_content).ephemeral(true)
+                })
+        })
+        .await
+    {
+        tracing::error!(
+            "couldn't respond to slash command for user `{}` due to `{}`",
+            command_interaction.user.tag(),
+            err,
+        );
+    }
+}
+
+/// `reactinator policy` related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Used outside of a guild.
+    NotInGuild,
+    /// Command of the role policy does not exist.
+    UnknownCommand(String),
+    /// Options are missing or invalid.
+    Options(OptionError),
+}
+
+impl From<OptionError> for Error {
+    fn from(err: OptionError) -> Self {
+        Error::Options(err)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reactinator policy: ")?;
+        match self {
+            Error::NotInGuild => write!(f, "can only be used in a server"),
+            Error::UnknownCommand(name) => write!(f, "unknown command `{}`", name),
+            Error::Options(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,181 @@
+//! Message command to react to the message with the emojis entered
+//! in a modal.
+
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::{
+        application::{
+            command::CommandType,
+            interaction::{modal::ModalSubmitInteraction, InteractionResponseType},
+        },
+        prelude::{application_command::ApplicationCommandInteraction, Permissions},
+    },
+};
+
+use crate::BotContext;
+
+use super::{
+    modal_input_text, modal_message_target, open_message_command_modal, react_to_message_with,
+    Command, Requester,
+};
+
+/// `React with…` message command.
+pub struct ReactWith;
+
+/// Name of the command.
+pub const NAME: &str = "React with…";
+
+/// Input `emoji` of the modal.
+const INPUT_EMOJI: &str = "emoji";
+
+#[async_trait]
+impl Command for ReactWith {
+    fn register(command: &mut CreateApplicationCommand, _bot_context: &BotContext) -> Self {
+        command
+            .name(NAME)
+            .kind(CommandType::Message)
+            .default_member_permissions(Permissions::ADD_REACTIONS);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        context: &serenity::prelude::Context,
+        _bot_context: &BotContext,
+    ) {
+        open_message_command_modal(
+            NAME,
+            "React with",
+            INPUT_EMOJI,
+            "Emojis, separated by spaces",
+            command_interaction,
+            context,
+        )
+        .await;
+    }
+
+    async fn modal_submit(
+        &self,
+        modal_interaction: &ModalSubmitInteraction,
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let mut react_with_err = None;
+
+        let message_target = modal_message_target(modal_interaction);
+        if message_target.is_none() {
+            react_with_err = Some(Error::InvalidModalCustomId(
+                modal_interaction.data.custom_id.clone(),
+            ));
+        }
+
+        let emojis = match modal_input_text(modal_interaction, INPUT_EMOJI) {
+            Some(emojis) if !emojis.trim().is_empty() => Some(emojis),
+            _ => {
+                react_with_err = Some(Error::RequiresEmoji);
+                None
+            }
+        };
+
+        if let Some(react_with_err) = &react_with_err {
+            tracing::error!(
+                target: "react_with",
+                "user `{}` - {}",
+                modal_interaction.user.tag(),
+                react_with_err,
+            );
+        }
+
+        if let Err(err) = modal_interaction
+            .create_interaction_response(&context.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message
+                            .content(if let Some(err) = &react_with_err {
+                                format!("error: {}", err)
+                            } else {
+                                format!(
+                                    "Reacting to message `{}` with {}.",
+                                    message_target.unwrap().1,
+                                    emojis.unwrap(),
+                                )
+                            })
+                            .ephemeral(true)
+                    })
+            })
+            .await
+        {
+            tracing::error!(
+                "couldn't respond to modal submit for user `{}` due to `{}`",
+                modal_interaction.user.tag(),
+                err,
+            );
+        }
+
+        if let (Some(emojis), Some((channel_id, message_id))) = (emojis, message_target) {
+            let report = react_to_message_with(
+                channel_id,
+                message_id,
+                emojis,
+                Requester {
+                    user: &modal_interaction.user,
+                    member: modal_interaction.member.as_ref(),
+                    command: &format!("{} {}", NAME, emojis),
+                },
+                context,
+                bot_context,
+            )
+            .await;
+
+            if report.has_failures() {
+                tracing::error!(
+                    target: "react_with",
+                    "user `{}` - couldn't add all reactions to `{}`:\n{}",
+                    modal_interaction.user.tag(),
+                    message_id,
+                    report,
+                );
+            }
+
+            if let Err(err) = modal_interaction
+                .edit_original_interaction_response(&context.http, |response| {
+                    response.content(report.response_content(message_id))
+                })
+                .await
+            {
+                tracing::error!(
+                    "couldn't edit interaction response message to \
+                     modal submit for user `{}` due to `{}`",
+                    modal_interaction.user.tag(),
+                    err,
+                );
+            }
+        }
+    }
+}
+
+/// `React with…` related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Custom ID of the submitted modal is malformed.
+    InvalidModalCustomId(String),
+    /// No emoji is given.
+    RequiresEmoji,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "react_with: ")?;
+        match self {
+            Error::InvalidModalCustomId(custom_id) => {
+                write!(f, "invalid modal custom id `{}`", custom_id)
+            }
+            Error::RequiresEmoji => write!(f, "requires emoji"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,68 @@
+//! Command grouping the administration of the bot in a guild.
+
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::{
+        application::interaction::application_command::ApplicationCommandInteraction,
+        prelude::Permissions,
+    },
+};
+
+use crate::BotContext;
+
+use super::{command_toggles, config, filter, policy, subcommands::Subcommands, Command};
+
+/// `reactinator` command.
+pub struct Reactinator {
+    /// Subcommands of the command.
+    subcommands: Subcommands,
+}
+
+#[async_trait]
+impl Command for Reactinator {
+    fn register(command: &mut CreateApplicationCommand, bot_context: &BotContext) -> Self {
+        command
+            .name("reactinator")
+            .description("Manage the bot in this server.")
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .dm_permission(false);
+        let mut subcommands = Subcommands::new();
+        subcommands.group(
+            command,
+            "policy",
+            "Who can use the commands and where.",
+            |group| policy::register(group, bot_context),
+        );
+        subcommands.group(
+            command,
+            "filter",
+            "Words and emojis the bot refuses to react with.",
+            |group| filter::register(group, bot_context),
+        );
+        subcommands.group(
+            command,
+            "config",
+            "Timeouts, notifications and spelling of the bot.",
+            |group| config::register(group, bot_context),
+        );
+        subcommands.group(
+            command,
+            "commands",
+            "Which commands are enabled in this server.",
+            |group| command_toggles::register(group, bot_context),
+        );
+        Self { subcommands }
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        self.subcommands
+            .interaction(command_interaction, context, bot_context)
+            .await;
+    }
+}
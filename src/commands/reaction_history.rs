@@ -0,0 +1,247 @@
+//! Command for moderators to look up the reactions the bot was asked
+//! to add, see [`AuditLog`](crate::audit_log::AuditLog).
+
+use std::borrow::Cow;
+
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateApplicationCommandOption},
+    model::{
+        application::interaction::application_command::ApplicationCommandInteraction, prelude::*,
+    },
+};
+
+use crate::{
+    audit_log::{AuditEntry, AuditQuery},
+    message_target::MessageTarget,
+    permissions::can_view_channel,
+    BotContext,
+};
+
+use super::{
+    options::{command_options, OptionError},
+    Command,
+};
+
+/// `reaction_history` command.
+pub struct ReactionHistory;
+
+/// Option `user`.
+const OPTION_USER: &str = "user";
+
+/// Option `channel`.
+const OPTION_CHANNEL: &str = "channel";
+
+/// Option `message`.
+const OPTION_MESSAGE: &str = "message";
+
+/// Option `limit`.
+const OPTION_LIMIT: &str = "limit";
+
+/// Option `export`.
+const OPTION_EXPORT: &str = "export";
+
+/// Entries shown when no limit is given.
+const DEFAULT_LIMIT: usize = 10;
+
+/// Entries shown at most, exports are not limited.
+const MAX_LIMIT: usize = 25;
+
+/// Restrict the option `limit` to `1..=MAX_LIMIT`.
+fn limit_range(option: &mut CreateApplicationCommandOption) -> &mut CreateApplicationCommandOption {
+    option.min_int_value(1).max_int_value(MAX_LIMIT)
+}
+
+command_options! {
+    /// Options of the `reaction_history` command.
+    struct Options {
+        /// Only requests made by the user.
+        user(OPTION_USER): Option<UserId>,
+        /// Only requests reacting in the channel.
+        channel(OPTION_CHANNEL): Option<ChannelId>,
+        /// Only requests reacting to the message, by ID or link.
+        message(OPTION_MESSAGE): Option<MessageTarget>,
+        /// Number of requests to show, newest first.
+        limit(OPTION_LIMIT): Option<usize> => limit_range,
+        /// Attach every matching request as JSON lines.
+        export(OPTION_EXPORT): Option<bool>,
+    }
+}
+
+#[async_trait]
+impl Command for ReactionHistory {
+    fn register(command: &mut CreateApplicationCommand, _bot_context: &BotContext) -> Self {
+        command
+            .name("reaction_history")
+            .description("Show who made the bot react to what.")
+            .default_member_permissions(Permissions::MANAGE_MESSAGES)
+            .dm_permission(false);
+        Options::register(command);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let history = async {
+            let options = Options::parse(&command_interaction.data.options)?;
+            let guild_id = command_interaction.guild_id.ok_or(Error::NotInGuild)?;
+            let member = command_interaction
+                .member
+                .as_ref()
+                .ok_or(Error::NotInGuild)?;
+            let guild = context
+                .cache
+                .guild(guild_id)
+                .ok_or(Error::UnknownGuild(guild_id))?;
+            // Moderators only see the requests in the channels they can
+            // view themselves.
+            let visible = |channel_id| can_view_channel(&guild, channel_id, member);
+            if let Some(channel_id) = options.channel {
+                if !visible(channel_id) {
+                    return Err(Error::ChannelNotVisible(channel_id));
+                }
+            }
+            let message_id = match options.message {
+                Some(MessageTarget::Id { message_id, .. }) => Some(message_id),
+                Some(_) => return Err(Error::RequiresMessageId),
+                None => None,
+            };
+            let query = AuditQuery {
+                user_id: options.user,
+                channel_id: options.channel,
+                message_id,
+            };
+            let export = options.export.unwrap_or(false);
+            let limit = if export {
+                usize::MAX
+            } else {
+                options.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+            };
+
+            let entries = bot_context
+                .audit_log
+                .read()
+                .await
+                .query(guild_id, &query, limit, visible);
+            tracing::info!(
+                target: "reaction_history",
+                "user `{}` - found `{}` entries in guild `{}` for {:?}",
+                command_interaction.user.tag(),
+                entries.len(),
+                guild_id,
+                query,
+            );
+            Ok((entries, export))
+        }
+        .await;
+
+        let (content, export) = match history {
+            Ok((entries, true)) => (
+                format!("Exported {} requests.", entries.len()),
+                Some(
+                    entries
+                        .iter()
+                        .map(|entry| entry.to_json_line() + "\n")
+                        .collect::<String>(),
+                ),
+            ),
+            Ok((entries, false)) => (history_content(&entries), None),
+            Err(err) => {
+                tracing::error!(
+                    target: "reaction_history",
+                    "user `{}` - {}",
+                    command_interaction.user.tag(),
+                    err,
+                );
+                (format!("error: {}", err), None)
+            }
+        };
+
+        if let Err(err) = command_interaction
+            .create_interaction_response(&context.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        if let Some(export) = export {
+                            message.add_file(AttachmentType::Bytes {
+                                data: Cow::Owned(export.into_bytes()),
+                                filename: "reaction_history.jsonl".to_string(),
+                            });
+                        }
+                        message
+                            .content(content)
+                            .allowed_mentions(|allowed_mentions| allowed_mentions.empty_parse())
+                            .ephemeral(true)
+                    })
+            })
+            .await
+        {
+            tracing::error!(
+                target: "reaction_history",
+                "couldn't respond to `reaction_history` for user `{}` due to `{}`",
+                command_interaction.user.tag(),
+                err
+            );
+        }
+    }
+}
+
+/// Response content listing the entries, cut to fit in a message.
+fn history_content(entries: &[AuditEntry]) -> String {
+    if entries.is_empty() {
+        return "No reactions found.".to_string();
+    }
+
+    let mut content = String::new();
+    for entry in entries {
+        let line = entry.to_string();
+        if content.len() + line.len() + 1 > 2000 {
+            break;
+        }
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
+
+/// `reaction_history` related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Used outside of a guild.
+    NotInGuild,
+    /// Message is not given by its ID or link.
+    RequiresMessageId,
+    /// Guild is not in the cache.
+    UnknownGuild(GuildId),
+    /// Member cannot view the channel.
+    ChannelNotVisible(ChannelId),
+    /// Options are missing or invalid.
+    Options(OptionError),
+}
+
+impl From<OptionError> for Error {
+    fn from(err: OptionError) -> Self {
+        Error::Options(err)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "reaction_history: ")?;
+        match self {
+            Error::NotInGuild => write!(f, "can only be used in a server"),
+            Error::RequiresMessageId => write!(f, "message must be a message ID or link"),
+            Error::UnknownGuild(guild_id) => write!(f, "server `{}` is not known", guild_id),
+            Error::ChannelNotVisible(channel_id) => {
+                write!(f, "you cannot view the channel {}", channel_id.mention())
+            }
+            Error::Options(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
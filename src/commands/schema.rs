@@ -0,0 +1,192 @@
+//! Schema of the registered commands, to skip registering commands
+//! that did not change.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use serenity::json::Value;
+
+/// Hash of the schema of the commands, given either as built by
+/// [`serenity::builder::CreateApplicationCommands`] or as returned by
+/// Discord for the registered commands.
+///
+/// Every field is considered except the ones assigned by Discord, with
+/// the fields left at their default dropped, so that both forms hash
+/// the same. The order of the commands does not matter.
+pub fn schema_hash(commands: &[Value]) -> u64 {
+    let mut commands = commands
+        .iter()
+        .map(normalize_command)
+        .map(|command| command.to_string())
+        .collect::<Vec<_>>();
+    commands.sort();
+
+    let mut hasher = DefaultHasher::new();
+    commands.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Fields assigned by Discord to the registered commands, or resolved
+/// for the locale of the request.
+const VOLATILE_FIELDS: &[&str] = &[
+    "id",
+    "application_id",
+    "guild_id",
+    "version",
+    "name_localized",
+    "description_localized",
+];
+
+/// Normalize the command so that the built and registered forms
+/// match.
+fn normalize_command(command: &Value) -> Value {
+    let mut command = command.clone();
+    if let Some(command) = command.as_object_mut() {
+        command.entry("type").or_insert_with(|| Value::from(1));
+        // Sent as a string but returned as either a string or a
+        // number.
+        if let Some(permissions) = command.get_mut("default_member_permissions") {
+            if let Some(number) = permissions.as_u64() {
+                *permissions = Value::from(number.to_string());
+            }
+        }
+    }
+    normalize_value(&command)
+}
+
+/// Normalize the value, dropping the volatile fields and the fields
+/// left at their default, with the fields of objects sorted.
+fn normalize_value(value: &Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut fields = object
+                .iter()
+                .filter(|(name, _)| !VOLATILE_FIELDS.contains(&name.as_str()))
+                .map(|(name, value)| (name, normalize_field(name, value)))
+                .filter(|(name, value)| !is_default(name, value))
+                .collect::<Vec<_>>();
+            fields.sort_by_key(|(name, _)| *name);
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name.clone(), value))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.iter().map(normalize_value).collect()),
+        Value::Number(_) => normalize_number(value),
+        _ => value.clone(),
+    }
+}
+
+/// Normalize the value of the field of an object.
+fn normalize_field(name: &str, value: &Value) -> Value {
+    match (name, normalize_value(value)) {
+        ("channel_types", Value::Array(mut channel_types)) => {
+            channel_types.sort_by(|a, b| a.as_f64().partial_cmp(&b.as_f64()).unwrap());
+            Value::Array(channel_types)
+        }
+        (_, value) => value,
+    }
+}
+
+/// Check if the field has the value Discord assumes when it is
+/// missing.
+fn is_default(name: &str, value: &Value) -> bool {
+    match (name, value) {
+        (_, Value::Null) => true,
+        (_, Value::Array(values)) => values.is_empty(),
+        (_, Value::Object(object)) => object.is_empty(),
+        ("description", Value::String(description)) => description.is_empty(),
+        ("required" | "autocomplete" | "nsfw", Value::Bool(value)) => !value,
+        ("dm_permission", Value::Bool(value)) => *value,
+        _ => false,
+    }
+}
+
+/// Numbers may come back as integers or floats, compare them as
+/// floats. Other values are kept as they are.
+fn normalize_number(value: &Value) -> Value {
+    value.as_f64().map_or_else(|| value.clone(), Value::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::json::json;
+
+    use super::schema_hash;
+
+    /// Built and registered forms of the same commands hash the same,
+    /// changes do not.
+    #[test]
+    fn schema_hash_01() {
+        let built = [
+            json!({
+                "name": "add_reaction",
+                "description": "Add reaction(s).",
+                "options": [{
+                    "type": 4,
+                    "name": "offset",
+                    "description": "Offset.",
+                    "min_value": 1,
+                }],
+            }),
+            json!({
+                "name": "React with…",
+                "type": 3,
+                "default_member_permissions": "32",
+            }),
+        ];
+        let registered = [
+            json!({
+                "id": "2",
+                "type": 3,
+                "name": "React with…",
+                "description": "",
+                "default_member_permissions": 32,
+                "dm_permission": true,
+                "nsfw": false,
+                "name_localizations": null,
+                "version": "1",
+            }),
+            json!({
+                "id": "1",
+                "type": 1,
+                "name": "add_reaction",
+                "description": "Add reaction(s).",
+                "options": [{
+                    "type": 4,
+                    "name": "offset",
+                    "description": "Offset.",
+                    "required": false,
+                    "choices": [],
+                    "min_value": 1.0,
+                }],
+                "version": "1",
+            }),
+        ];
+        assert_eq!(schema_hash(&built), schema_hash(&registered));
+
+        let mut changed = built.clone();
+        changed[0]["options"][0]["min_value"] = json!(2);
+        assert_ne!(schema_hash(&built), schema_hash(&changed));
+        assert_ne!(schema_hash(&built), schema_hash(&built[..1]));
+
+        [
+            ("dm_permission", json!(false)),
+            ("nsfw", json!(true)),
+            ("name_localizations", json!({ "fr": "ajouter_reaction" })),
+        ]
+        .into_iter()
+        .for_each(|(name, value)| {
+            let mut changed = built.clone();
+            changed[0][name] = value;
+            assert_ne!(schema_hash(&built), schema_hash(&changed), "{}", name);
+        });
+        let mut changed = built.clone();
+        changed[0]["options"][0]["max_length"] = json!(10);
+        assert_ne!(schema_hash(&built), schema_hash(&changed));
+    }
+}
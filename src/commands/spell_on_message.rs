@@ -0,0 +1,221 @@
+//! Message command to spell the text entered in a modal as reactions
+//! on the message.
+
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::{
+        application::{
+            command::CommandType,
+            interaction::{modal::ModalSubmitInteraction, InteractionResponseType},
+        },
+        prelude::{application_command::ApplicationCommandInteraction, Permissions},
+    },
+};
+
+use crate::{content_filter::Blocked, BotContext};
+
+use super::{
+    modal_input_text, modal_message_target, open_message_command_modal, react_to_message_with,
+    text_to_reactions::text_to_emojis, Command, Requester,
+};
+
+/// `Spell on this message` message command.
+pub struct SpellOnMessage;
+
+/// Name of the command.
+pub const NAME: &str = "Spell on this message";
+
+/// Input `text` of the modal.
+const INPUT_TEXT: &str = "text";
+
+#[async_trait]
+impl Command for SpellOnMessage {
+    fn register(command: &mut CreateApplicationCommand, _bot_context: &BotContext) -> Self {
+        command
+            .name(NAME)
+            .kind(CommandType::Message)
+            .default_member_permissions(Permissions::ADD_REACTIONS);
+        Self
+    }
+
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        context: &serenity::prelude::Context,
+        _bot_context: &BotContext,
+    ) {
+        open_message_command_modal(
+            NAME,
+            "Spell on this message",
+            INPUT_TEXT,
+            "Text to convert to reactions",
+            command_interaction,
+            context,
+        )
+        .await;
+    }
+
+    async fn modal_submit(
+        &self,
+        modal_interaction: &ModalSubmitInteraction,
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) {
+        let mut spell_on_message_err = None;
+
+        let message_target = modal_message_target(modal_interaction);
+        if message_target.is_none() {
+            spell_on_message_err = Some(Error::InvalidModalCustomId(
+                modal_interaction.data.custom_id.clone(),
+            ));
+        }
+
+        let content_filter = bot_context
+            .get_content_filter(modal_interaction.guild_id)
+            .await;
+        let alternatives = bot_context
+            .get_guild_config(modal_interaction.guild_id)
+            .await
+            .alternatives;
+        let emoji_text = match modal_input_text(modal_interaction, INPUT_TEXT) {
+            // Spelled reactions lose the gaps between the words, so the
+            // words are checked before.
+            Some(text) if !text.trim().is_empty() => match content_filter
+                .check_text(text)
+                .map(|()| text_to_emojis(text, alternatives))
+            {
+                Err(blocked) => {
+                    spell_on_message_err = Some(Error::Blocked(blocked));
+                    None
+                }
+                Ok(Some(emoji_text)) => {
+                    tracing::info!(
+                        "converted `{}` to `{}` for user `{}`",
+                        text,
+                        emoji_text,
+                        modal_interaction.user.tag()
+                    );
+                    Some(emoji_text)
+                }
+                Ok(None) => {
+                    spell_on_message_err = Some(Error::CouldNotConvertTextToEmojis);
+                    None
+                }
+            },
+            _ => {
+                spell_on_message_err = Some(Error::RequiresText);
+                None
+            }
+        };
+
+        if let Some(spell_on_message_err) = &spell_on_message_err {
+            tracing::error!(
+                target: "spell_on_message",
+                "user `{}` - {}",
+                modal_interaction.user.tag(),
+                spell_on_message_err,
+            );
+        }
+
+        if let Err(err) = modal_interaction
+            .create_interaction_response(&context.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message
+                            .content(if let Some(err) = &spell_on_message_err {
+                                format!("error: {}", err)
+                            } else {
+                                format!(
+                                    "Reacting to message `{}` with {}.",
+                                    message_target.unwrap().1,
+                                    emoji_text.as_ref().unwrap(),
+                                )
+                            })
+                            .ephemeral(true)
+                    })
+            })
+            .await
+        {
+            tracing::error!(
+                "couldn't respond to modal submit for user `{}` due to `{}`",
+                modal_interaction.user.tag(),
+                err,
+            );
+        }
+
+        if let (Some(emoji_text), Some((channel_id, message_id))) = (emoji_text, message_target) {
+            let report = react_to_message_with(
+                channel_id,
+                message_id,
+                &emoji_text,
+                Requester {
+                    user: &modal_interaction.user,
+                    member: modal_interaction.member.as_ref(),
+                    command: &format!(
+                        "{} {}",
+                        NAME,
+                        modal_input_text(modal_interaction, INPUT_TEXT).unwrap_or_default()
+                    ),
+                },
+                context,
+                bot_context,
+            )
+            .await;
+
+            if report.has_failures() {
+                tracing::error!(
+                    target: "spell_on_message",
+                    "user `{}` - couldn't add all reactions to `{}`:\n{}",
+                    modal_interaction.user.tag(),
+                    message_id,
+                    report,
+                );
+            }
+
+            if let Err(err) = modal_interaction
+                .edit_original_interaction_response(&context.http, |response| {
+                    response.content(report.response_content(message_id))
+                })
+                .await
+            {
+                tracing::error!(
+                    "couldn't edit interaction response message to \
+                     modal submit for user `{}` due to `{}`",
+                    modal_interaction.user.tag(),
+                    err,
+                );
+            }
+        }
+    }
+}
+
+/// `Spell on this message` related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Custom ID of the submitted modal is malformed.
+    InvalidModalCustomId(String),
+    /// Text is empty.
+    RequiresText,
+    /// Text cannot be spelled with the emojis.
+    CouldNotConvertTextToEmojis,
+    /// Text is refused by the content filter of the guild.
+    Blocked(Blocked),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "spell_on_message: ")?;
+        match self {
+            Error::InvalidModalCustomId(custom_id) => {
+                write!(f, "invalid modal custom id `{}`", custom_id)
+            }
+            Error::RequiresText => write!(f, "requires text"),
+            Error::CouldNotConvertTextToEmojis => write!(f, "could not convert text to emojis"),
+            Error::Blocked(blocked) => write!(f, "{}", blocked),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
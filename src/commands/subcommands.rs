@@ -0,0 +1,262 @@
+//! Subcommands and subcommand groups of a command, each handled by
+//! its own [`Subcommand`].
+
+use std::collections::HashMap;
+
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateApplicationCommandOption},
+    model::application::{
+        command::CommandOptionType,
+        interaction::{
+            application_command::{ApplicationCommandInteraction, CommandDataOption},
+            autocomplete::AutocompleteInteraction,
+        },
+    },
+    prelude::Context,
+};
+
+use crate::BotContext;
+
+/// Subcommand of a [`super::Command`].
+#[async_trait]
+pub trait Subcommand: Send + Sync + 'static {
+    /// Register the subcommand, the kind of the option is already set.
+    fn register(subcommand: &mut CreateApplicationCommandOption, bot_context: &BotContext) -> Self
+    where
+        Self: Sized;
+
+    /// Interaction with the subcommand, `options` are the options
+    /// given to the subcommand.
+    async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        options: &[CommandDataOption],
+        context: &Context,
+        bot_context: &BotContext,
+    );
+
+    /// Autocompletion of an option of the subcommand, `options` are
+    /// the options given to the subcommand.
+    async fn autocomplete(
+        &self,
+        _autocomplete_interaction: &AutocompleteInteraction,
+        _options: &[CommandDataOption],
+        _context: &Context,
+        _bot_context: &BotContext,
+    ) {
+    }
+}
+
+/// Subcommands of a command, routed by their path such as `search`
+/// or `config timeout` for a subcommand in a group.
+#[derive(Default)]
+pub struct Subcommands(HashMap<String, Box<dyn Subcommand>>);
+
+impl Subcommands {
+    /// Create a new set of [`Subcommands`].
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Register the subcommand `S` on the command.
+    pub fn subcommand<S: Subcommand>(
+        &mut self,
+        command: &mut CreateApplicationCommand,
+        bot_context: &BotContext,
+    ) -> &mut Self {
+        let (option, subcommand) = create_subcommand::<S>(bot_context);
+        self.0.insert(option_name(&option), Box::new(subcommand));
+        command.add_option(option);
+        self
+    }
+
+    /// Register the subcommand group `name` on the command, with the
+    /// subcommands added by `register`.
+    pub fn group(
+        &mut self,
+        command: &mut CreateApplicationCommand,
+        name: &str,
+        description: &str,
+        register: impl FnOnce(&mut SubcommandGroup),
+    ) -> &mut Self {
+        let mut group = SubcommandGroup {
+            option: CreateApplicationCommandOption::default(),
+            subcommands: Vec::new(),
+        };
+        group
+            .option
+            .kind(CommandOptionType::SubCommandGroup)
+            .name(name)
+            .description(description);
+        register(&mut group);
+
+        self.0.extend(
+            group
+                .subcommands
+                .into_iter()
+                .map(|(subcommand_name, subcommand)| {
+                    (format!("{} {}", name, subcommand_name), subcommand)
+                }),
+        );
+        command.add_option(group.option);
+        self
+    }
+
+    /// Route the interaction to the subcommand.
+    pub async fn interaction(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        context: &Context,
+        bot_context: &BotContext,
+    ) {
+        match self.route(&command_interaction.data.options) {
+            Some((subcommand, options)) => {
+                subcommand
+                    .interaction(command_interaction, options, context, bot_context)
+                    .await
+            }
+            None => {
+                tracing::error!(
+                    "unknown subcommand of {}: {:?}",
+                    command_interaction.data.name,
+                    subcommand_path(&command_interaction.data.options)
+                );
+            }
+        }
+    }
+
+    /// Route the autocompletion to the subcommand.
+    pub async fn autocomplete(
+        &self,
+        autocomplete_interaction: &AutocompleteInteraction,
+        context: &Context,
+        bot_context: &BotContext,
+    ) {
+        match self.route(&autocomplete_interaction.data.options) {
+            Some((subcommand, options)) => {
+                subcommand
+                    .autocomplete(autocomplete_interaction, options, context, bot_context)
+                    .await
+            }
+            None => {
+                tracing::error!(
+                    "unknown subcommand of {}: {:?}",
+                    autocomplete_interaction.data.name,
+                    subcommand_path(&autocomplete_interaction.data.options)
+                );
+            }
+        }
+    }
+
+    /// Find the subcommand given by the options of the command, along
+    /// with the options given to it.
+    fn route<'a>(
+        &self,
+        options: &'a [CommandDataOption],
+    ) -> Option<(&dyn Subcommand, &'a [CommandDataOption])> {
+        let (path, options) = subcommand_path(options)?;
+        self.0
+            .get(&path)
+            .map(|subcommand| (subcommand.as_ref(), options))
+    }
+}
+
+/// Subcommand group being registered, see [`Subcommands::group()`].
+pub struct SubcommandGroup {
+    /// Option of the group.
+    option: CreateApplicationCommandOption,
+    /// Subcommands of the group by name.
+    subcommands: Vec<(String, Box<dyn Subcommand>)>,
+}
+
+impl SubcommandGroup {
+    /// Register the subcommand `S` in the group.
+    pub fn subcommand<S: Subcommand>(&mut self, bot_context: &BotContext) -> &mut Self {
+        let (option, subcommand) = create_subcommand::<S>(bot_context);
+        self.subcommands
+            .push((option_name(&option), Box::new(subcommand)));
+        self.option.add_sub_option(option);
+        self
+    }
+}
+
+/// Register the subcommand `S`.
+fn create_subcommand<S: Subcommand>(
+    bot_context: &BotContext,
+) -> (CreateApplicationCommandOption, S) {
+    let mut option = CreateApplicationCommandOption::default();
+    option.kind(CommandOptionType::SubCommand);
+    let subcommand = S::register(&mut option, bot_context);
+    (option, subcommand)
+}
+
+/// Name of the registered option.
+fn option_name(option: &CreateApplicationCommandOption) -> String {
+    option
+        .0
+        .get("name")
+        .and_then(|name| name.as_str())
+        .expect("subcommand must have a name")
+        .to_string()
+}
+
+/// Path of the subcommand given by the options of the command, such
+/// as `config timeout`, along with the options given to it.
+pub fn subcommand_path(options: &[CommandDataOption]) -> Option<(String, &[CommandDataOption])> {
+    let option = options.first()?;
+    match option.kind {
+        CommandOptionType::SubCommand => Some((option.name.clone(), &option.options)),
+        CommandOptionType::SubCommandGroup => {
+            let subcommand = option
+                .options
+                .first()
+                .filter(|subcommand| subcommand.kind == CommandOptionType::SubCommand)?;
+            Some((
+                format!("{} {}", option.name, subcommand.name),
+                &subcommand.options,
+            ))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::{
+        json::{json, prelude::from_value},
+        model::application::interaction::application_command::CommandDataOption,
+    };
+
+    use super::subcommand_path;
+
+    /// Subcommands are found at the top level or in a group, along
+    /// with their options.
+    #[test]
+    fn subcommand_path_01() {
+        let options: Vec<CommandDataOption> = from_value(json!([{
+            "name": "search",
+            "type": 1,
+            "options": [{"name": "query", "type": 3, "value": "smile"}],
+        }]))
+        .unwrap();
+        let (path, options) = subcommand_path(&options).unwrap();
+        assert_eq!(path, "search");
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].name, "query");
+
+        let options: Vec<CommandDataOption> = from_value(json!([{
+            "name": "config",
+            "type": 2,
+            "options": [{"name": "timeout", "type": 1}],
+        }]))
+        .unwrap();
+        let (path, options) = subcommand_path(&options).unwrap();
+        assert_eq!(path, "config timeout");
+        assert!(options.is_empty());
+
+        let options: Vec<CommandDataOption> =
+            from_value(json!([{"name": "query", "type": 3, "value": "smile"}])).unwrap();
+        assert!(subcommand_path(&options).is_none());
+    }
+}
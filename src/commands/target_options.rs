@@ -0,0 +1,199 @@
+//! Options shared by the commands reacting to a message, selecting
+//! the message to react to.
+
+use serenity::model::{
+    application::interaction::application_command::ApplicationCommandInteraction, prelude::*,
+};
+
+use crate::{
+    message_history::{MessageFilter, MessageHistory},
+    message_target::{MessageTarget, MessageTargetError},
+    BotContext,
+};
+
+use super::options::command_options;
+
+/// Option `message_id`.
+const OPTION_MESSAGE_ID: &str = "message_id";
+
+/// Option `offset`.
+const OPTION_OFFSET: &str = "offset";
+
+/// Option `user`.
+const OPTION_USER: &str = "user";
+
+/// Option `contains`.
+const OPTION_CONTAINS: &str = "contains";
+
+command_options! {
+    /// Options selecting the message to react to.
+    pub struct TargetOptions {
+        /// Message ID or link, ^ for the messages above or starter for the thread's post. Defaults to last.
+        message_id(OPTION_MESSAGE_ID): Option<MessageTarget>,
+        /// Number of messages up to react to, 1 is the last message.
+        offset(OPTION_OFFSET): Option<usize> => |option| {
+            option
+                .min_int_value(1)
+                .max_int_value(MessageHistory::DEFAULT_MAX_MESSAGES_PER_CHANNEL)
+        },
+        /// Only count the messages of this user.
+        user(OPTION_USER): Option<UserId>,
+        /// Only count the messages containing this text.
+        contains(OPTION_CONTAINS): Option<String>,
+    }
+}
+
+impl TargetOptions {
+    /// Resolve the options to the [`ChannelId`] and [`MessageId`] of
+    /// the message to react to.
+    pub async fn resolve(
+        &self,
+        command_interaction: &ApplicationCommandInteraction,
+        context: &serenity::prelude::Context,
+        bot_context: &BotContext,
+    ) -> Result<(ChannelId, MessageId), TargetError> {
+        if self.contains.is_some() && !bot_context.message_history.read().await.keeps_content() {
+            return Err(TargetError::MessageContentUnavailable);
+        }
+
+        let message_filter = MessageFilter::new(self.user, self.contains.as_deref())
+            .with_guild_settings(
+                bot_context
+                    .get_guild_settings(command_interaction.guild_id)
+                    .await,
+            );
+
+        let message_target = match (self.message_id, self.offset) {
+            (Some(_), Some(_)) => return Err(TargetError::MessageIdAndOffsetProvided),
+            (Some(_), None) if message_filter.has_criteria() => {
+                return Err(TargetError::MessageIdAndFilterProvided)
+            }
+            (Some(message_target), None) => message_target,
+            (None, Some(0)) => return Err(TargetError::NoMessageAtOffset(0)),
+            (None, Some(offset)) => MessageTarget::Relative(offset),
+            (None, None) => MessageTarget::LATEST,
+        };
+
+        message_target
+            .check_access(
+                &context.cache,
+                command_interaction.guild_id,
+                command_interaction.member.as_ref(),
+            )
+            .map_err(TargetError::InaccessibleMessage)?;
+
+        if let MessageTarget::Relative(offset) = message_target {
+            // Any number of messages may not match the filter, so fetch
+            // the history unless it was fetched before.
+            let count = if message_filter.has_criteria() {
+                usize::MAX
+            } else {
+                offset
+            };
+            bot_context
+                .ensure_message_history(context, command_interaction.channel_id, count)
+                .await;
+        }
+
+        let resolved = message_target.resolve(
+            &context.cache,
+            command_interaction.channel_id,
+            &*bot_context.message_history.read().await,
+            &message_filter,
+        );
+
+        // Threads of text channels share the ID of their starter
+        // message only if they were started from one.
+        if let (MessageTarget::ThreadStarter, Some((channel_id, message_id))) =
+            (message_target, resolved)
+        {
+            if channel_id != command_interaction.channel_id
+                && context.cache.message(channel_id, message_id).is_none()
+                && context
+                    .http
+                    .get_message(channel_id.0, message_id.0)
+                    .await
+                    .is_err()
+            {
+                return Err(TargetError::NoThreadStarter);
+            }
+        }
+
+        resolved.ok_or(match message_target {
+            MessageTarget::ThreadStarter => TargetError::NotInThread,
+            MessageTarget::Relative(offset) if message_filter.has_criteria() => {
+                TargetError::NoMatchingMessage(offset)
+            }
+            MessageTarget::Relative(offset) if offset > 1 => TargetError::NoMessageAtOffset(offset),
+            _ => TargetError::NoLastMessageAvailableAndNoMessageIdProvided,
+        })
+    }
+}
+
+/// Errors of the [`TargetOptions`].
+#[derive(Debug)]
+pub enum TargetError {
+    /// Member cannot access the targeted message.
+    InaccessibleMessage(MessageTargetError),
+    /// Both a message ID and an offset were given.
+    MessageIdAndOffsetProvided,
+    /// User or contains were given with a message ID.
+    MessageIdAndFilterProvided,
+    /// Contains was given but the message content is not kept.
+    MessageContentUnavailable,
+    /// No message is known in the channel.
+    NoLastMessageAvailableAndNoMessageIdProvided,
+    /// History does not go back this many messages.
+    NoMessageAtOffset(usize),
+    /// Not this many messages match the filter.
+    NoMatchingMessage(usize),
+    /// Starter was targeted outside of a public thread.
+    NotInThread,
+    /// Thread was not started from a message.
+    NoThreadStarter,
+}
+
+impl std::fmt::Display for TargetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetError::InaccessibleMessage(err) => write!(f, "inaccessible message: {}", err),
+            TargetError::MessageIdAndOffsetProvided => {
+                write!(f, "only one of message id and offset can be provided")
+            }
+            TargetError::MessageIdAndFilterProvided => {
+                write!(f, "user and contains cannot be used with a message id")
+            }
+            TargetError::MessageContentUnavailable => {
+                write!(
+                    f,
+                    "contains is unavailable since the bot does not read message content"
+                )
+            }
+            TargetError::NoLastMessageAvailableAndNoMessageIdProvided => {
+                write!(f, "no last message available and no message id provided")
+            }
+            TargetError::NoMessageAtOffset(offset) => {
+                write!(f, "no message available {} messages up", offset)
+            }
+            TargetError::NoMatchingMessage(1) => write!(f, "no matching message available"),
+            TargetError::NoMatchingMessage(offset) => {
+                write!(
+                    f,
+                    "no matching message available {} matching messages up",
+                    offset
+                )
+            }
+            TargetError::NotInThread => {
+                write!(
+                    f,
+                    "starter message is only available in public threads and forum posts"
+                )
+            }
+            TargetError::NoThreadStarter => {
+                write!(f, "thread was not started from a message")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TargetError {}
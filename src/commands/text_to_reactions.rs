@@ -7,19 +7,20 @@ use lazy_static::lazy_static;
 use serenity::{
     async_trait,
     builder::CreateApplicationCommand,
-    json::Value,
     model::{
-        application::interaction::InteractionResponseType,
-        prelude::{
-            application_command::ApplicationCommandInteraction, command::CommandOptionType,
-            MessageId,
-        },
+        application::interaction::application_command::ApplicationCommandInteraction,
+        prelude::Permissions,
     },
 };
 
-use crate::BotContext;
+use crate::{content_filter::Blocked, BotContext};
 
-use super::{react_to_message_with, Command, ReactToMessageWithError};
+use super::{
+    options::{command_options, OptionError},
+    respond_and_react,
+    target_options::{TargetError, TargetOptions},
+    Command,
+};
 
 /// `text_to_reactions` command.
 pub struct TextToReactions;
@@ -27,8 +28,13 @@ pub struct TextToReactions;
 /// Option `text`.
 const OPTION_TEXT: &str = "text";
 
-/// Option `message_id`.
-const OPTION_MESSAGE_ID: &str = "message_id";
+command_options! {
+    /// Options of the `text_to_reactions` command.
+    struct Options {
+        /// Text to convert to reactions.
+        text(OPTION_TEXT): String,
+    }
+}
 
 #[async_trait]
 impl Command for TextToReactions {
@@ -39,172 +45,53 @@ impl Command for TextToReactions {
                 "Text as list of reactions to the given \
                  message or last message on the channel.",
             )
-            .create_option(|command_option| {
-                command_option
-                    .required(true)
-                    .kind(CommandOptionType::String)
-                    .name(OPTION_TEXT)
-                    .description("Text to convert to reactions.")
-            })
-            .create_option(|command_option| {
-                command_option
-                    .kind(CommandOptionType::String)
-                    .name(OPTION_MESSAGE_ID)
-                    .description("Message ID to react to. Defaults to last message on channel.")
-            });
+            .default_member_permissions(Permissions::ADD_REACTIONS);
+        Options::register(command);
+        TargetOptions::register(command);
         Self
     }
 
     async fn interaction(
-        &mut self,
+        &self,
         command_interaction: &ApplicationCommandInteraction,
         context: &serenity::prelude::Context,
         bot_context: &BotContext,
     ) {
-        let mut text_to_reactions_err = None;
-
-        let text = match command_interaction
-            .data
-            .options
-            .iter()
-            .find_map(|option| (option.name == OPTION_TEXT).then_some(option.value.as_ref()))
-        {
-            Some(Some(text)) => match text.as_str() {
-                Some(text) => Some(text),
-                None => {
-                    text_to_reactions_err = Some(Error::TextMustBeProvidedInString(text.clone()));
-                    None
-                }
-            },
-            _ => {
-                text_to_reactions_err = Some(Error::RequiresText);
-                None
+        let reaction = async {
+            let options = Options::parse(&command_interaction.data.options)?;
+            let target_options = TargetOptions::parse(&command_interaction.data.options)?;
+            if options.text.trim().is_empty() {
+                return Err(Error::RequiresText);
             }
-        };
-
-        let emoji_text = match text {
-            Some(text) => match text_to_emojis(&text) {
-                Some(emoji_text) => {
-                    tracing::info!(
-                        "converted `{}` to `{}` for user `{}`",
-                        text,
-                        emoji_text,
-                        command_interaction.user.tag()
-                    );
-                    Some(emoji_text)
-                }
-                None => {
-                    text_to_reactions_err = Some(Error::CouldNotConvertTextToEmojis);
-                    None
-                }
-            },
-            None => None,
-        };
-
-        let message_id =
-            match command_interaction.data.options.iter().find_map(|option| {
-                (option.name == OPTION_MESSAGE_ID).then_some(option.value.as_ref())
-            }) {
-                Some(Some(message_id)) => match message_id.as_str() {
-                    Some(message_id) => match message_id.parse::<u64>().ok() {
-                        Some(message_id) => Some(MessageId(message_id)),
-                        None => {
-                            text_to_reactions_err =
-                                Some(Error::InvalidMessageId(message_id.to_string()));
-                            None
-                        }
-                    },
-                    None => {
-                        text_to_reactions_err =
-                            Some(Error::MessageIdMustBeString(message_id.clone()));
-                        None
-                    }
-                },
-                _ => None,
-            };
-
-        let message_id = match message_id {
-            Some(message_id) => Some(message_id),
-            None => match bot_context
-                .last_message_ids
-                .read()
+            // Spelled reactions lose the gaps between the words, so the
+            // words are checked before.
+            bot_context
+                .get_content_filter(command_interaction.guild_id)
                 .await
-                .get(&command_interaction.channel_id)
-                .copied()
-            {
-                Some(message_id) => Some(message_id),
-                None => {
-                    text_to_reactions_err =
-                        Some(Error::NoLastMessageAvailableAndNoMessageIdProvided);
-                    None
-                }
-            },
-        };
-
-        if let Err(err) = command_interaction
-            .create_interaction_response(&context.http, |response| {
-                response
-                    .kind(InteractionResponseType::ChannelMessageWithSource)
-                    .interaction_response_data(|message| {
-                        message
-                            .content(if let Some(err) = text_to_reactions_err.take() {
-                                format!("error: {}", err)
-                            } else {
-                                format!(
-                                    "Don't forget to react to message `{}` \
-                                     yourself for the reactions {}.",
-                                    message_id.unwrap(),
-                                    emoji_text.as_ref().unwrap(),
-                                )
-                            })
-                            .ephemeral(true)
-                    })
-            })
-            .await
-        {
-            tracing::error!(
-                "couldn't respond error message to slash command for user `{}` due to `{}`",
-                command_interaction.user.tag(),
-                err
-            );
-        }
-
-        if let (Some(emoji_text), Some(message_id)) = (emoji_text, message_id) {
-            if let Err(err) = react_to_message_with(
-                message_id,
-                &emoji_text,
-                command_interaction,
-                context,
-                bot_context,
-            )
-            .await
-            {
-                text_to_reactions_err = Some(err.into());
-            }
-        }
+                .check_text(&options.text)
+                .map_err(Error::Blocked)?;
 
-        if let Some(text_to_reactions_err) = &text_to_reactions_err {
-            tracing::error!(
-                target: "text_to_reactions",
-                "user `{}` - {}",
-                command_interaction.user.tag(),
-                text_to_reactions_err,
+            let alternatives = bot_context
+                .get_guild_config(command_interaction.guild_id)
+                .await
+                .alternatives;
+            let emoji_text = text_to_emojis(&options.text, alternatives)
+                .ok_or(Error::CouldNotConvertTextToEmojis)?;
+            tracing::info!(
+                "converted `{}` to `{}` for user `{}`",
+                options.text,
+                emoji_text,
+                command_interaction.user.tag()
             );
 
-            if let Err(err) = command_interaction
-                .edit_original_interaction_response(&context.http, |response| {
-                    response.content(format!("error: {}", text_to_reactions_err))
-                })
-                .await
-            {
-                tracing::error!(
-                    "couldn't edit interaction response message to \
-                     slash command for user `{}` due to `{}`",
-                    command_interaction.user.tag(),
-                    err,
-                );
-            }
+            let target = target_options
+                .resolve(command_interaction, context, bot_context)
+                .await?;
+            Ok((target, emoji_text))
         }
+        .await;
+
+        respond_and_react(command_interaction, context, bot_context, reaction).await;
     }
 }
 
@@ -302,8 +189,9 @@ lazy_static! {
     };
 }
 
-/// Text to emoji compatible text.
-pub fn text_to_emojis(text: &str) -> Option<String> {
+/// Text to emoji compatible text, repeated characters falling back
+/// to their [`TEXT_TO_EMOJIS_ALTERNATIVES`] if `alternatives` is set.
+pub fn text_to_emojis(text: &str, alternatives: bool) -> Option<String> {
     let mut used_characters: HashMap<char, usize> = HashMap::new();
     Some(
         text.to_lowercase()
@@ -322,6 +210,9 @@ pub fn text_to_emojis(text: &str) -> Option<String> {
 
                         Some(emoji)
                     } else {
+                        if !alternatives {
+                            return None;
+                        }
                         let alternative =
                             *TEXT_TO_EMOJIS_ALTERNATIVES.get(&c)?.iter().find(|c| {
                                 match used_characters.get(*c) {
@@ -362,13 +253,28 @@ pub fn text_to_emojis(text: &str) -> Option<String> {
 /// `text_to_reactions` related errors.
 #[derive(Debug)]
 pub enum Error {
+    /// Text is empty.
     RequiresText,
-    TextMustBeProvidedInString(Value),
-    MessageIdMustBeString(Value),
-    InvalidMessageId(String),
-    NoLastMessageAvailableAndNoMessageIdProvided,
+    /// Text cannot be spelled with the emojis.
     CouldNotConvertTextToEmojis,
-    ReactToMessage(ReactToMessageWithError),
+    /// Text is refused by the content filter of the guild.
+    Blocked(Blocked),
+    /// Options are missing or invalid.
+    Options(OptionError),
+    /// Target message cannot be resolved.
+    Target(TargetError),
+}
+
+impl From<OptionError> for Error {
+    fn from(err: OptionError) -> Self {
+        Error::Options(err)
+    }
+}
+
+impl From<TargetError> for Error {
+    fn from(err: TargetError) -> Self {
+        Error::Target(err)
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -376,30 +282,16 @@ impl std::fmt::Display for Error {
         write!(f, "text_to_reactions: ")?;
         match self {
             Error::RequiresText => write!(f, "requires text"),
-            Error::TextMustBeProvidedInString(value) => {
-                write!(f, "text must be provided in a string, got `{}`", value)
-            }
-            Error::MessageIdMustBeString(value) => {
-                write!(f, "message id must be a string, got `{}`", value)
-            }
-            Error::InvalidMessageId(value) => write!(f, "invalid message id, got `{}`", value),
-            Error::NoLastMessageAvailableAndNoMessageIdProvided => {
-                write!(f, "no last message available and no message id provided")
-            }
             Error::CouldNotConvertTextToEmojis => write!(f, "could not convert text to emojis"),
-            Error::ReactToMessage(err) => write!(f, "{}", err),
+            Error::Blocked(blocked) => write!(f, "{}", blocked),
+            Error::Options(err) => write!(f, "{}", err),
+            Error::Target(err) => write!(f, "{}", err),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
-impl From<ReactToMessageWithError> for Error {
-    fn from(err: ReactToMessageWithError) -> Self {
-        Self::ReactToMessage(err)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use crate::commands::text_to_reactions::TEXT_TO_EMOJIS_EMOJI_NAME_TO_EMOJI;
@@ -409,10 +301,12 @@ mod tests {
     /// Basic test of alternatives.
     #[test]
     fn text_to_emojis_01() {
-        assert_eq!(text_to_emojis("a").unwrap(), "🇦");
-        assert_eq!(text_to_emojis("aa").unwrap(), "🇦 🅰️");
-        assert_eq!(text_to_emojis("aaa").unwrap(), "🇦 🅰️ 4️⃣");
-        assert_eq!(text_to_emojis("aaaa"), None);
+        assert_eq!(text_to_emojis("a", true).unwrap(), "🇦");
+        assert_eq!(text_to_emojis("aa", true).unwrap(), "🇦 🅰️");
+        assert_eq!(text_to_emojis("aaa", true).unwrap(), "🇦 🅰️ 4️⃣");
+        assert_eq!(text_to_emojis("aaaa", true), None);
+        assert_eq!(text_to_emojis("aa", false).unwrap(), "🇦 🅰️");
+        assert_eq!(text_to_emojis("aaa", false), None);
     }
 
     /// Test all the characters, does not test the alternatives.
@@ -440,6 +334,6 @@ mod tests {
             .collect::<Vec<_>>()
             .join(" ");
 
-        assert_eq!(text_to_emojis(&char_string).unwrap(), emoji_string);
+        assert_eq!(text_to_emojis(&char_string, true).unwrap(), emoji_string);
     }
 }
@@ -0,0 +1,240 @@
+//! Per guild configuration of the tunables of the bot.
+//!
+//! Every tunable is a [`ConfigKey`] with a default and a validated
+//! value. Subsystems read the configuration when they use it, such as
+//! the [`ReactionTracker`] which is given it with every reaction to
+//! track, so changes apply without a restart.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::reaction_tracker::ReactionTracker;
+
+/// Key of a value of the [`GuildConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigKey {
+    ReactionTimeout,
+    RemovalNotice,
+    Alternatives,
+}
+
+impl ConfigKey {
+    /// All the keys, in the order they are listed.
+    pub const ALL: [ConfigKey; 3] = [
+        ConfigKey::ReactionTimeout,
+        ConfigKey::RemovalNotice,
+        ConfigKey::Alternatives,
+    ];
+
+    /// Name of the key.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigKey::ReactionTimeout => "reaction_timeout",
+            ConfigKey::RemovalNotice => "removal_notice",
+            ConfigKey::Alternatives => "alternatives",
+        }
+    }
+
+    /// What the key changes.
+    pub fn description(&self) -> &'static str {
+        match self {
+            ConfigKey::ReactionTimeout => {
+                "seconds users have to react before the bot removes its reactions"
+            }
+            ConfigKey::RemovalNotice => "how users are told their reactions were removed",
+            ConfigKey::Alternatives => {
+                "spell repeated letters with lookalike characters, such as 4 for a"
+            }
+        }
+    }
+
+    /// Values the key accepts.
+    pub fn expected(&self) -> String {
+        match self {
+            ConfigKey::ReactionTimeout => format!(
+                "a number of seconds from {} to {}",
+                GuildConfig::REACTION_TIMEOUT_RANGE.start(),
+                GuildConfig::REACTION_TIMEOUT_RANGE.end()
+            ),
+            ConfigKey::RemovalNotice => "dm or none".to_string(),
+            ConfigKey::Alternatives => "on or off".to_string(),
+        }
+    }
+
+    /// Key of the name.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|key| key.name() == name)
+    }
+}
+
+impl std::fmt::Display for ConfigKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// How users are told the bot removed the reactions they did not
+/// react with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemovalNotice {
+    /// Direct message to the user.
+    #[default]
+    Dm,
+    /// Reactions are removed silently.
+    None,
+}
+
+impl std::fmt::Display for RemovalNotice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemovalNotice::Dm => write!(f, "dm"),
+            RemovalNotice::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Configuration of a guild.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildConfig {
+    /// Seconds users have to react before the bot removes its
+    /// reactions.
+    pub reaction_timeout_secs: u64,
+    /// How users are told their reactions were removed.
+    pub removal_notice: RemovalNotice,
+    /// Spell repeated letters with lookalike characters, see
+    /// [`TEXT_TO_EMOJIS_ALTERNATIVES`](crate::commands::text_to_reactions::TEXT_TO_EMOJIS_ALTERNATIVES).
+    pub alternatives: bool,
+}
+
+impl GuildConfig {
+    /// Seconds [`ConfigKey::ReactionTimeout`] may be set to.
+    pub const REACTION_TIMEOUT_RANGE: std::ops::RangeInclusive<u64> = 1..=300;
+
+    /// Time users have to react before the bot removes its reactions.
+    pub fn reaction_timeout(&self) -> Duration {
+        Duration::from_secs(self.reaction_timeout_secs)
+    }
+
+    /// Value of the key.
+    pub fn get(&self, key: ConfigKey) -> String {
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        match key {
+            ConfigKey::ReactionTimeout => self.reaction_timeout_secs.to_string(),
+            ConfigKey::RemovalNotice => self.removal_notice.to_string(),
+            ConfigKey::Alternatives => on_off(self.alternatives).to_string(),
+        }
+    }
+
+    /// Set the key to the value, leaving the configuration unchanged
+    /// if the value is invalid.
+    pub fn set(&mut self, key: ConfigKey, value: &str) -> Result<(), ConfigError> {
+        let value = value.trim();
+        let invalid = || ConfigError::InvalidValue(key, value.to_string());
+        match key {
+            ConfigKey::ReactionTimeout => {
+                self.reaction_timeout_secs = value
+                    .parse()
+                    .ok()
+                    .filter(|secs| Self::REACTION_TIMEOUT_RANGE.contains(secs))
+                    .ok_or_else(invalid)?;
+            }
+            ConfigKey::RemovalNotice => {
+                self.removal_notice = match value.to_lowercase().as_str() {
+                    "dm" => RemovalNotice::Dm,
+                    "none" => RemovalNotice::None,
+                    _ => return Err(invalid()),
+                };
+            }
+            ConfigKey::Alternatives => {
+                self.alternatives = match value.to_lowercase().as_str() {
+                    "on" | "true" | "yes" => true,
+                    "off" | "false" | "no" => false,
+                    _ => return Err(invalid()),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    /// Reset the key to its default.
+    pub fn reset(&mut self, key: ConfigKey) {
+        let default = Self::default();
+        match key {
+            ConfigKey::ReactionTimeout => {
+                self.reaction_timeout_secs = default.reaction_timeout_secs
+            }
+            ConfigKey::RemovalNotice => self.removal_notice = default.removal_notice,
+            ConfigKey::Alternatives => self.alternatives = default.alternatives,
+        }
+    }
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        Self {
+            reaction_timeout_secs: ReactionTracker::TIMEOUT.as_secs(),
+            removal_notice: RemovalNotice::Dm,
+            alternatives: true,
+        }
+    }
+}
+
+/// Errors of the [`GuildConfig`].
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Value is not accepted by the key, see [`ConfigKey::expected()`].
+    InvalidValue(ConfigKey, String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::InvalidValue(key, value) => write!(
+                f,
+                "invalid {} `{}`, expected {}",
+                key,
+                value,
+                key.expected()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigError, ConfigKey, GuildConfig, RemovalNotice};
+
+    /// Values are validated and reset to their defaults.
+    #[test]
+    fn guild_config_01() {
+        let mut guild_config = GuildConfig::default();
+        guild_config.set(ConfigKey::ReactionTimeout, "30").unwrap();
+        guild_config.set(ConfigKey::RemovalNotice, "None").unwrap();
+        guild_config.set(ConfigKey::Alternatives, "off").unwrap();
+        assert_eq!(guild_config.reaction_timeout().as_secs(), 30);
+        assert_eq!(guild_config.removal_notice, RemovalNotice::None);
+        assert_eq!(guild_config.get(ConfigKey::Alternatives), "off");
+
+        assert!(matches!(
+            guild_config.set(ConfigKey::ReactionTimeout, "0"),
+            Err(ConfigError::InvalidValue(ConfigKey::ReactionTimeout, _))
+        ));
+        assert!(guild_config.set(ConfigKey::RemovalNotice, "email").is_err());
+        assert_eq!(guild_config.reaction_timeout_secs, 30);
+
+        ConfigKey::ALL
+            .into_iter()
+            .for_each(|key| guild_config.reset(key));
+        assert_eq!(guild_config, GuildConfig::default());
+        assert_eq!(
+            ConfigKey::parse("reaction_timeout"),
+            Some(ConfigKey::ReactionTimeout)
+        );
+        assert_eq!(ConfigKey::parse("timeout"), None);
+    }
+}
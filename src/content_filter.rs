@@ -0,0 +1,467 @@
+//! Per guild filter of the words and emojis the bot refuses to react
+//! with.
+
+use std::collections::{BTreeSet, HashMap};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serenity::model::prelude::*;
+
+use crate::commands::text_to_reactions::{
+    TEXT_TO_EMOJIS_ALTERNATIVES, TEXT_TO_EMOJIS_CHAR_TO_EMOJI_NAME,
+    TEXT_TO_EMOJIS_EMOJI_NAME_TO_EMOJI,
+};
+
+/// Words blocked unless the guild disables the default list.
+pub const DEFAULT_WORDS: &[&str] = &[
+    "chink", "cunt", "faggot", "gook", "kike", "nigga", "nigger", "retard", "tranny", "wetback",
+];
+
+/// Emojis blocked unless the guild disables the default list.
+pub const DEFAULT_EMOJIS: &[&str] = &["🖕"];
+
+lazy_static! {
+    /// Digit or symbol of [`TEXT_TO_EMOJIS_ALTERNATIVES`] to the
+    /// first letter it stands for, so `4` becomes `a` and `$` becomes
+    /// `s`. Letters are never replaced by other letters.
+    static ref CANONICAL_CHARS: HashMap<char, char> = {
+        let mut canonical_chars: HashMap<char, char> = HashMap::new();
+        for (c, alternatives) in TEXT_TO_EMOJIS_ALTERNATIVES.iter() {
+            for alternative in alternatives.iter().filter(|c| !c.is_alphabetic()) {
+                let canonical = canonical_chars.entry(*alternative).or_insert(*c);
+                *canonical = (*canonical).min(*c);
+            }
+        }
+        canonical_chars
+    };
+
+    /// Emoji of [`TEXT_TO_EMOJIS_CHAR_TO_EMOJI_NAME`] to the [`char`]
+    /// it spells.
+    static ref EMOJI_TO_CHAR: HashMap<String, char> = TEXT_TO_EMOJIS_CHAR_TO_EMOJI_NAME
+        .iter()
+        .flat_map(|(c, emoji_names)| {
+            emoji_names
+                .iter()
+                .filter_map(|emoji_name| TEXT_TO_EMOJIS_EMOJI_NAME_TO_EMOJI.get(emoji_name.as_str()))
+                .map(|emoji| (normalize_emoji(emoji), *c))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+}
+
+/// Lowercase the text and replace the digits and symbols standing for
+/// letters, dropping anything but letters and digits and repeated
+/// characters, so leetspeak and spacing spell the same as the plain
+/// word.
+pub fn normalize_text(text: &str) -> String {
+    let mut normalized = String::new();
+    text.to_lowercase()
+        .chars()
+        .map(|c| *CANONICAL_CHARS.get(&c).unwrap_or(&c))
+        .filter(|c| c.is_alphanumeric())
+        .for_each(|c| {
+            if !normalized.ends_with(c) {
+                normalized.push(c);
+            }
+        });
+    normalized
+}
+
+/// Tokens of the text a blocked word must spell as a whole: the words
+/// of the text, with and without the punctuation around them, the runs
+/// of single characters since spacing a word out is a common way to
+/// sneak it past, and the whole text without spacing. Only letters,
+/// digits and the symbols standing for letters are kept.
+fn tokens(text: &str) -> Vec<Vec<char>> {
+    let words = text
+        .split_whitespace()
+        .map(|word| {
+            word.to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphanumeric() || CANONICAL_CHARS.contains_key(c))
+                .collect::<Vec<_>>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut tokens = words.clone();
+    tokens.extend(words.iter().filter_map(|word| {
+        let start = word.iter().position(|c| c.is_alphanumeric())?;
+        let end = word.iter().rposition(|c| c.is_alphanumeric())?;
+        (end + 1 - start < word.len()).then(|| word[start..=end].to_vec())
+    }));
+    let mut run = Vec::new();
+    for word in &words {
+        if word.len() == 1 {
+            run.extend_from_slice(word);
+        } else {
+            if run.len() > 1 {
+                tokens.push(run.clone());
+            }
+            run.clear();
+        }
+    }
+    if run.len() > 1 {
+        tokens.push(run);
+    }
+    tokens.push(words.concat());
+    tokens
+}
+
+/// Check if the character may stand for the letter of a blocked word.
+fn stands_for(c: char, letter: char) -> bool {
+    c == letter
+        || TEXT_TO_EMOJIS_ALTERNATIVES
+            .get(&letter)
+            .is_some_and(|alternatives| alternatives.contains(&c))
+}
+
+/// Check if the whole token spells the word, normalized with
+/// [`normalize_text()`]. Every character of the word may be repeated
+/// in the token.
+fn spells(token: &[char], word: &[char]) -> bool {
+    // Numbers of characters of the word spelled by the token so far.
+    let mut spelled = vec![false; word.len() + 1];
+    spelled[0] = true;
+    for c in token {
+        let mut next = vec![false; word.len() + 1];
+        for (count, _) in spelled.iter().enumerate().filter(|(_, spelled)| **spelled) {
+            if count > 0 && stands_for(*c, word[count - 1]) {
+                next[count] = true;
+            }
+            if count < word.len() && stands_for(*c, word[count]) {
+                next[count + 1] = true;
+            }
+        }
+        spelled = next;
+    }
+    !token.is_empty() && spelled[word.len()]
+}
+
+/// Drop the variation selectors of the emoji, they are optional.
+fn normalize_emoji(emoji: &str) -> String {
+    emoji.chars().filter(|c| *c != '\u{fe0f}').collect()
+}
+
+/// Text spelled by the reactions, as converted by
+/// [`text_to_emojis()`](crate::commands::text_to_reactions::text_to_emojis).
+fn spell_reactions(reaction_types: &[ReactionType]) -> String {
+    reaction_types
+        .iter()
+        .filter_map(|reaction_type| match reaction_type {
+            ReactionType::Unicode(emoji) => EMOJI_TO_CHAR.get(&normalize_emoji(emoji)).copied(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Word or emoji of a [`ContentFilter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterEntry {
+    /// Word, blocked in any spelling [`normalize_text()`] maps to it.
+    Word(String),
+    /// Unicode emoji.
+    Emoji(String),
+    /// Custom emoji, by ID so renaming it does not matter.
+    CustomEmoji(EmojiId),
+}
+
+impl FilterEntry {
+    /// Parse the entry, text with letters or digits being a word.
+    pub fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return None;
+        }
+        if entry.starts_with('<') {
+            return match ReactionType::try_from(entry) {
+                Ok(ReactionType::Custom { id, .. }) => Some(Self::CustomEmoji(id)),
+                _ => None,
+            };
+        }
+        if entry.chars().any(|c| c.is_ascii_alphanumeric()) {
+            let word = normalize_text(entry);
+            (!word.is_empty()).then_some(Self::Word(word))
+        } else {
+            Some(Self::Emoji(normalize_emoji(entry)))
+        }
+    }
+}
+
+impl std::fmt::Display for FilterEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterEntry::Word(word) => write!(f, "||{}||", word),
+            FilterEntry::Emoji(emoji) => write!(f, "{}", emoji),
+            FilterEntry::CustomEmoji(emoji_id) => write!(f, "<:_:{}>", emoji_id),
+        }
+    }
+}
+
+/// Content filter of a guild.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentFilter {
+    /// Block [`DEFAULT_WORDS`] and [`DEFAULT_EMOJIS`] too.
+    pub use_defaults: bool,
+    /// Blocked words, normalized with [`normalize_text()`].
+    pub words: BTreeSet<String>,
+    /// Blocked unicode emojis, without variation selectors.
+    pub emojis: BTreeSet<String>,
+    /// Blocked custom emojis.
+    pub custom_emojis: BTreeSet<EmojiId>,
+}
+
+impl ContentFilter {
+    /// Add the entry, returns `false` if it was already present.
+    pub fn add(&mut self, entry: FilterEntry) -> bool {
+        match entry {
+            FilterEntry::Word(word) => self.words.insert(word),
+            FilterEntry::Emoji(emoji) => self.emojis.insert(emoji),
+            FilterEntry::CustomEmoji(emoji_id) => self.custom_emojis.insert(emoji_id),
+        }
+    }
+
+    /// Remove the entry, returns `false` if it was not present.
+    pub fn remove(&mut self, entry: &FilterEntry) -> bool {
+        match entry {
+            FilterEntry::Word(word) => self.words.remove(word),
+            FilterEntry::Emoji(emoji) => self.emojis.remove(emoji),
+            FilterEntry::CustomEmoji(emoji_id) => self.custom_emojis.remove(emoji_id),
+        }
+    }
+
+    /// Blocked words, normalized.
+    fn blocked_words(&self) -> impl Iterator<Item = String> + '_ {
+        let defaults = if self.use_defaults {
+            DEFAULT_WORDS
+        } else {
+            &[]
+        };
+        defaults
+            .iter()
+            .map(|word| normalize_text(word))
+            .chain(self.words.iter().cloned())
+    }
+
+    /// Check if the emoji is blocked.
+    fn is_blocked_emoji(&self, emoji: &str) -> bool {
+        let emoji = normalize_emoji(emoji);
+        self.emojis.contains(&emoji)
+            || self.use_defaults
+                && DEFAULT_EMOJIS
+                    .iter()
+                    .any(|default| normalize_emoji(default) == emoji)
+    }
+
+    /// Check that no word of the text, nor the text as a whole,
+    /// spells a blocked word. Words merely containing a blocked word
+    /// are fine.
+    pub fn check_text(&self, text: &str) -> Result<(), Blocked> {
+        let tokens = tokens(text);
+        if self.blocked_words().any(|word| {
+            let word = word.chars().collect::<Vec<_>>();
+            tokens.iter().any(|token| spells(token, &word))
+        }) {
+            Err(Blocked::Word)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check that none of the reactions is a blocked emoji and that
+    /// together they spell no blocked word.
+    pub fn check_reactions(&self, reaction_types: &[ReactionType]) -> Result<(), Blocked> {
+        if let Some(reaction_type) = reaction_types
+            .iter()
+            .find(|reaction_type| match reaction_type {
+                ReactionType::Custom { id, .. } => self.custom_emojis.contains(id),
+                ReactionType::Unicode(emoji) => self.is_blocked_emoji(emoji),
+                _ => false,
+            })
+        {
+            return Err(Blocked::Emoji(reaction_type.clone()));
+        }
+
+        self.check_text(&spell_reactions(reaction_types))
+    }
+}
+
+impl Default for ContentFilter {
+    fn default() -> Self {
+        Self {
+            use_defaults: true,
+            words: BTreeSet::new(),
+            emojis: BTreeSet::new(),
+            custom_emojis: BTreeSet::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for ContentFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "default list: {}",
+            if self.use_defaults { "on" } else { "off" }
+        )?;
+        let entries = self
+            .words
+            .iter()
+            .cloned()
+            .map(FilterEntry::Word)
+            .chain(self.emojis.iter().cloned().map(FilterEntry::Emoji))
+            .chain(
+                self.custom_emojis
+                    .iter()
+                    .copied()
+                    .map(FilterEntry::CustomEmoji),
+            )
+            .map(|entry| entry.to_string())
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            write!(f, "blocked: none")
+        } else {
+            write!(f, "blocked: {}", entries.join(", "))
+        }
+    }
+}
+
+/// Reason a [`ContentFilter`] refuses the reactions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Blocked {
+    /// Text or reactions spell a blocked word.
+    Word,
+    /// Emoji is blocked.
+    Emoji(ReactionType),
+}
+
+impl std::fmt::Display for Blocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Blocked::Word => write!(f, "the text or reactions spell a blocked word"),
+            Blocked::Emoji(reaction_type) => write!(f, "{} is blocked", reaction_type),
+        }
+    }
+}
+
+impl std::error::Error for Blocked {}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::prelude::*;
+
+    use crate::commands::text_to_reactions::text_to_emojis;
+
+    use super::{normalize_text, Blocked, ContentFilter, FilterEntry};
+
+    /// Leetspeak and spacing normalize to the plain word.
+    #[test]
+    fn content_filter_01() {
+        assert_eq!(normalize_text("B 4 D"), "bad");
+        assert_eq!(normalize_text("b4$$"), normalize_text("bass"));
+        assert_eq!(normalize_text("L1L"), "lil");
+
+        let mut content_filter = ContentFilter::default();
+        assert!(content_filter.add(FilterEntry::parse("bad").unwrap()));
+        assert!(!content_filter.add(FilterEntry::parse("BAD").unwrap()));
+        assert_eq!(content_filter.check_text("not 8 4 d"), Err(Blocked::Word));
+        assert!(content_filter.check_text("good").is_ok());
+
+        assert!(content_filter.remove(&FilterEntry::parse("b4d").unwrap()));
+        assert!(content_filter.check_text("bad").is_ok());
+    }
+
+    /// Words containing a blocked word, or spanning words that do, are
+    /// not blocked.
+    #[test]
+    fn content_filter_03() {
+        let content_filter = ContentFilter::default();
+        [
+            "big ok",
+            "going ok",
+            "Scunthorpe",
+            "flame retardant",
+            "kill",
+        ]
+        .into_iter()
+        .for_each(|text| assert!(content_filter.check_text(text).is_ok(), "{}", text));
+        ["g o o k", "R3T4RD", "the retard", "c.u.n.t"]
+            .into_iter()
+            .for_each(|text| {
+                assert_eq!(
+                    content_filter.check_text(text),
+                    Err(Blocked::Word),
+                    "{}",
+                    text
+                )
+            });
+
+        let mut content_filter = ContentFilter::default();
+        content_filter.add(FilterEntry::parse("kill").unwrap());
+        assert_eq!(content_filter.check_text("k1ll"), Err(Blocked::Word));
+        assert!(content_filter.check_text("kiii").is_ok());
+    }
+
+    /// Reactions are checked as emojis and as the text they spell.
+    #[test]
+    fn content_filter_02() {
+        let mut content_filter = ContentFilter::default();
+        content_filter.add(FilterEntry::parse("bad").unwrap());
+        content_filter.add(FilterEntry::parse("<:blocked:5>").unwrap());
+        content_filter.add(FilterEntry::parse("🍆").unwrap());
+
+        let reaction_types = |emoji_text: &str| {
+            emoji_text
+                .split_whitespace()
+                .map(|emoji| ReactionType::try_from(emoji).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            content_filter.check_reactions(&reaction_types(&text_to_emojis("bad", true).unwrap())),
+            Err(Blocked::Word)
+        );
+        assert!(content_filter
+            .check_reactions(&reaction_types(&text_to_emojis("bat", true).unwrap()))
+            .is_ok());
+        assert!(content_filter
+            .check_reactions(&reaction_types(&text_to_emojis("big ok", true).unwrap()))
+            .is_ok());
+        assert!(matches!(
+            content_filter.check_reactions(&reaction_types("👍 <:blocked:5>")),
+            Err(Blocked::Emoji(_))
+        ));
+        assert!(matches!(
+            content_filter.check_reactions(&reaction_types("🍆")),
+            Err(Blocked::Emoji(_))
+        ));
+        assert!(matches!(
+            content_filter.check_reactions(&reaction_types("🖕")),
+            Err(Blocked::Emoji(_))
+        ));
+
+        content_filter.use_defaults = false;
+        assert!(content_filter
+            .check_reactions(&reaction_types("🖕"))
+            .is_ok());
+    }
+
+    /// Blocked words are caught inside longer sentences, which the
+    /// commands check before spelling them as reactions.
+    #[test]
+    fn content_filter_04() {
+        let content_filter = ContentFilter::default();
+        ["ur a cunt", "you are a R3T4RD ok", "go away, kike!"]
+            .into_iter()
+            .for_each(|text| {
+                assert_eq!(
+                    content_filter.check_text(text),
+                    Err(Blocked::Word),
+                    "{}",
+                    text
+                )
+            });
+        assert!(content_filter.check_text("ur a good bot").is_ok());
+    }
+}
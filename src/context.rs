@@ -3,53 +3,528 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use serenity::model::prelude::*;
-use tokio::sync::RwLock;
+use serenity::{cache::Cache, model::prelude::*, prelude::Context};
+use tokio::sync::{broadcast, Mutex, RwLock};
+
+use crate::{
+    audit_log::{AuditEntry, AuditLog},
+    command_toggles::{CommandToggles, ToggleError},
+    config::{ConfigError, GuildConfig},
+    content_filter::ContentFilter,
+    emojis::EmojiIndex,
+    guild_settings::GuildSettings,
+    message_history::MessageHistory,
+    policy::{policy_channel_ids, policy_role_ids, GuildPolicy, PolicyError},
+    rate_limit::RateLimiter,
+    reaction_tracker::ReactionTracker,
+    storage::{GuildTable, MemoryStorage, Storage, StorageError, StorageWriter},
+};
 
 /// Bot's context.
 #[derive(Debug, Clone)]
 pub struct BotContext {
-    /// Last sent message's [`MessageId`] for the [`ChannelId`].
-    pub last_message_ids: Arc<RwLock<HashMap<ChannelId, MessageId>>>,
+    /// Recent messages of the channels.
+    pub message_history: Arc<RwLock<MessageHistory>>,
+
+    /// [`GuildId`] to its [`GuildSettings`], guilds without an entry
+    /// use the default settings.
+    pub guild_settings: Arc<RwLock<HashMap<GuildId, GuildSettings>>>,
+
+    /// [`GuildId`] to its [`GuildPolicy`], guilds without an entry
+    /// allow every command everywhere.
+    pub guild_policies: Arc<RwLock<HashMap<GuildId, GuildPolicy>>>,
+
+    /// [`GuildId`] to its [`ContentFilter`], guilds without an entry
+    /// and direct messages use the default filter.
+    pub content_filters: Arc<RwLock<HashMap<GuildId, ContentFilter>>>,
+
+    /// [`GuildId`] to its [`GuildConfig`], guilds without an entry
+    /// and direct messages use the default configuration.
+    pub guild_configs: Arc<RwLock<HashMap<GuildId, GuildConfig>>>,
+
+    /// [`GuildId`] to its [`CommandToggles`], guilds without an entry
+    /// have the commands enabled by default.
+    pub command_toggles: Arc<RwLock<HashMap<GuildId, CommandToggles>>>,
+
+    /// Guilds whose [`CommandToggles`] changed, so that their commands
+    /// are registered again, see
+    /// [`BotContext::update_command_toggles()`].
+    pub command_toggle_events: broadcast::Sender<GuildId>,
+
+    /// Threads joined by the bot since they were cached.
+    pub joined_threads: Arc<RwLock<HashSet<ChannelId>>>,
+
+    /// Custom emojis of the guilds.
+    pub emoji_index: Arc<RwLock<EmojiIndex>>,
+
+    /// Reactions the bot was asked to add in the guilds.
+    pub audit_log: Arc<RwLock<AuditLog>>,
 
-    /// [`GuildId`] to emoji name to [`Emoji`] mapping.
-    pub guild_emojis: Arc<RwLock<HashMap<GuildId, HashMap<String, Emoji>>>>,
+    /// Cooldowns of the commands and caps on the reactions.
+    pub rate_limiter: Arc<Mutex<RateLimiter>>,
 
-    /// Bot added reactions. Mapping from [`GuildId`] to the
-    /// [`BotAddedReactions`] for that guild.
-    pub bot_added_reactions:
-        Arc<RwLock<HashMap<GuildId, Vec<Arc<std::sync::RwLock<BotAddedReactions>>>>>>,
+    /// Reactions added by the bot on behalf of users.
+    pub reaction_tracker: ReactionTracker,
+
+    /// Writes the state that must survive restarts to the storage.
+    pub storage: StorageWriter,
 }
 
 impl BotContext {
-    /// Create a new [`BotContext`].
+    /// Capacity of the event channels, subscribers lagging further
+    /// behind miss events.
+    const EVENTS_CAPACITY: usize = 64;
+
+    /// Maximum number of channels per guild whose history is fetched
+    /// by [`BotContext::prewarm_message_history()`], the others are
+    /// fetched when a command needs them.
+    const PREWARM_CHANNELS_PER_GUILD: usize = 5;
+
+    /// Channels whose last message is older are not prewarmed.
+    const PREWARM_MAX_AGE_SECS: i64 = 24 * 60 * 60;
+
+    /// Create a new [`BotContext`] whose state only lives in memory.
+    ///
+    /// Must be called within a Tokio runtime since the
+    /// [`ReactionTracker`] task is spawned, it panics otherwise.
     pub fn new() -> Self {
-        Self {
-            last_message_ids: Arc::new(RwLock::new(HashMap::new())),
-            guild_emojis: Arc::new(RwLock::new(HashMap::new())),
-            bot_added_reactions: Arc::new(RwLock::new(HashMap::new())),
+        Self::with_storage(Arc::new(MemoryStorage::new()))
+            .expect("an empty memory storage always loads")
+    }
+
+    /// Create a new [`BotContext`] with the state loaded from the
+    /// storage, changes to the state are written back to it by a
+    /// [`StorageWriter`].
+    ///
+    /// Must be called within a Tokio runtime, see
+    /// [`BotContext::new()`].
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Result<Self, StorageError> {
+        let mut audit_log = AuditLog::default();
+        storage
+            .audit_entries()?
+            .into_iter()
+            .for_each(|entry| audit_log.insert(entry));
+        let stale_reactions = storage.pending_reactions()?;
+        let writer = StorageWriter::spawn(storage.clone());
+
+        Ok(Self {
+            message_history: Arc::new(RwLock::new(MessageHistory::default())),
+            guild_settings: Arc::new(RwLock::new(storage.load(GuildTable::Settings)?)),
+            guild_policies: Arc::new(RwLock::new(storage.load(GuildTable::Policies)?)),
+            content_filters: Arc::new(RwLock::new(storage.load(GuildTable::ContentFilters)?)),
+            reaction_tracker: ReactionTracker::spawn(writer.clone(), stale_reactions),
+            guild_configs: Arc::new(RwLock::new(storage.load(GuildTable::Configs)?)),
+            command_toggles: Arc::new(RwLock::new(storage.load(GuildTable::CommandToggles)?)),
+            command_toggle_events: broadcast::channel(Self::EVENTS_CAPACITY).0,
+            joined_threads: Arc::new(RwLock::new(HashSet::new())),
+            emoji_index: Arc::new(RwLock::new(EmojiIndex::default())),
+            audit_log: Arc::new(RwLock::new(audit_log)),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::default())),
+            storage: writer,
+        })
+    }
+
+    /// [`GuildSettings`] of the guild, the default settings outside of
+    /// guilds.
+    pub async fn get_guild_settings(&self, guild_id: Option<GuildId>) -> GuildSettings {
+        match guild_id {
+            Some(guild_id) => self
+                .guild_settings
+                .read()
+                .await
+                .get(&guild_id)
+                .cloned()
+                .unwrap_or_default(),
+            None => GuildSettings::default(),
+        }
+    }
+
+    /// [`ContentFilter`] of the guild, the default filter outside of
+    /// guilds.
+    pub async fn get_content_filter(&self, guild_id: Option<GuildId>) -> ContentFilter {
+        match guild_id {
+            Some(guild_id) => self
+                .content_filters
+                .read()
+                .await
+                .get(&guild_id)
+                .cloned()
+                .unwrap_or_default(),
+            None => ContentFilter::default(),
+        }
+    }
+
+    /// [`GuildConfig`] of the guild, the default configuration outside
+    /// of guilds.
+    pub async fn get_guild_config(&self, guild_id: Option<GuildId>) -> GuildConfig {
+        match guild_id {
+            Some(guild_id) => self
+                .guild_configs
+                .read()
+                .await
+                .get(&guild_id)
+                .cloned()
+                .unwrap_or_default(),
+            None => GuildConfig::default(),
+        }
+    }
+
+    /// Apply `update` to the [`GuildConfig`] of the guild, storing it
+    /// if it succeeds. Returns the updated configuration.
+    pub async fn update_guild_config(
+        &self,
+        guild_id: GuildId,
+        update: impl FnOnce(&mut GuildConfig) -> Result<(), ConfigError>,
+    ) -> Result<GuildConfig, ConfigError> {
+        let mut guild_configs = self.guild_configs.write().await;
+        let mut guild_config = guild_configs.get(&guild_id).cloned().unwrap_or_default();
+        update(&mut guild_config)?;
+
+        if guild_config == GuildConfig::default() {
+            guild_configs.remove(&guild_id);
+            self.storage
+                .save::<GuildConfig>(GuildTable::Configs, guild_id, None);
+        } else {
+            guild_configs.insert(guild_id, guild_config.clone());
+            self.storage
+                .save(GuildTable::Configs, guild_id, Some(&guild_config));
+        }
+        Ok(guild_config)
+    }
+
+    /// [`CommandToggles`] of the guild.
+    pub async fn get_command_toggles(&self, guild_id: GuildId) -> CommandToggles {
+        self.command_toggles
+            .read()
+            .await
+            .get(&guild_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Enable or disable the command in the guild, storing the change
+    /// and announcing it so that the commands of the guild are
+    /// registered again. Returns the updated [`CommandToggles`].
+    pub async fn update_command_toggles(
+        &self,
+        guild_id: GuildId,
+        update: impl FnOnce(&mut CommandToggles) -> Result<(), ToggleError>,
+    ) -> Result<CommandToggles, ToggleError> {
+        let mut command_toggles = self.command_toggles.write().await;
+        let mut guild_toggles = command_toggles.get(&guild_id).cloned().unwrap_or_default();
+        update(&mut guild_toggles)?;
+
+        if guild_toggles == CommandToggles::default() {
+            command_toggles.remove(&guild_id);
+            self.storage
+                .save::<CommandToggles>(GuildTable::CommandToggles, guild_id, None);
+        } else {
+            command_toggles.insert(guild_id, guild_toggles.clone());
+            self.storage
+                .save(GuildTable::CommandToggles, guild_id, Some(&guild_toggles));
+        }
+
+        // Nobody is listening before the bot is ready, the commands
+        // are registered with the stored toggles then.
+        let _ = self.command_toggle_events.send(guild_id);
+        Ok(guild_toggles)
+    }
+
+    /// Apply `update` to the [`GuildSettings`] of the guild and store
+    /// them. Returns the updated settings.
+    pub async fn update_guild_settings(
+        &self,
+        guild_id: GuildId,
+        update: impl FnOnce(&mut GuildSettings),
+    ) -> GuildSettings {
+        let mut guild_settings = self.guild_settings.write().await;
+        let mut settings = guild_settings.get(&guild_id).cloned().unwrap_or_default();
+        update(&mut settings);
+
+        if settings == GuildSettings::default() {
+            guild_settings.remove(&guild_id);
+            self.storage
+                .save::<GuildSettings>(GuildTable::Settings, guild_id, None);
+        } else {
+            guild_settings.insert(guild_id, settings.clone());
+            self.storage
+                .save(GuildTable::Settings, guild_id, Some(&settings));
+        }
+        settings
+    }
+
+    /// Apply `update` to the [`GuildPolicy`] of the guild and store
+    /// it. Returns the updated policy.
+    pub async fn update_guild_policy(
+        &self,
+        guild_id: GuildId,
+        update: impl FnOnce(&mut GuildPolicy),
+    ) -> GuildPolicy {
+        let mut guild_policies = self.guild_policies.write().await;
+        let mut guild_policy = guild_policies.get(&guild_id).cloned().unwrap_or_default();
+        update(&mut guild_policy);
+
+        if guild_policy == GuildPolicy::default() {
+            guild_policies.remove(&guild_id);
+            self.storage
+                .save::<GuildPolicy>(GuildTable::Policies, guild_id, None);
+        } else {
+            guild_policies.insert(guild_id, guild_policy.clone());
+            self.storage
+                .save(GuildTable::Policies, guild_id, Some(&guild_policy));
+        }
+        guild_policy
+    }
+
+    /// Apply `update` to the [`ContentFilter`] of the guild and store
+    /// it. Returns the updated filter.
+    pub async fn update_content_filter(
+        &self,
+        guild_id: GuildId,
+        update: impl FnOnce(&mut ContentFilter),
+    ) -> ContentFilter {
+        let mut content_filters = self.content_filters.write().await;
+        let mut content_filter = content_filters.get(&guild_id).cloned().unwrap_or_default();
+        update(&mut content_filter);
+
+        if content_filter == ContentFilter::default() {
+            content_filters.remove(&guild_id);
+            self.storage
+                .save::<ContentFilter>(GuildTable::ContentFilters, guild_id, None);
+        } else {
+            content_filters.insert(guild_id, content_filter.clone());
+            self.storage
+                .save(GuildTable::ContentFilters, guild_id, Some(&content_filter));
+        }
+        content_filter
+    }
+
+    /// Record the entry in the [`AuditLog`] and the storage.
+    pub async fn record_audit_entry(&self, entry: AuditEntry) {
+        let max_entries_per_guild = {
+            let mut audit_log = self.audit_log.write().await;
+            audit_log.insert(entry.clone());
+            audit_log.max_entries_per_guild()
+        };
+        self.storage
+            .insert_audit_entry(entry, max_entries_per_guild);
+    }
+
+    /// Check that the [`GuildPolicy`] of the member's guild allows
+    /// them to use the command in the channel. Members who can manage
+    /// the guild are always allowed, so they cannot lock themselves
+    /// out.
+    pub async fn check_policy(
+        &self,
+        cache: &Cache,
+        command_name: &str,
+        channel_id: ChannelId,
+        member: &Member,
+    ) -> Result<(), PolicyError> {
+        if member
+            .permissions
+            .is_some_and(|permissions| permissions.manage_guild())
+        {
+            return Ok(());
+        }
+
+        match self.guild_policies.read().await.get(&member.guild_id) {
+            Some(guild_policy) => guild_policy.check(
+                command_name,
+                &policy_channel_ids(cache, member.guild_id, channel_id),
+                &policy_role_ids(member),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Join the thread unless the bot is already a member of it.
+    /// Channels other than threads are ignored.
+    pub async fn ensure_thread_joined(
+        &self,
+        context: &Context,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) {
+        let is_member = match context.cache.guild(guild_id).and_then(|guild| {
+            guild
+                .threads
+                .iter()
+                .find(|thread| thread.id == channel_id)
+                .map(|thread| thread.member.is_some())
+        }) {
+            Some(is_member) => is_member,
+            None => return,
+        };
+
+        if is_member || self.joined_threads.read().await.contains(&channel_id) {
+            return;
+        }
+
+        match channel_id.join_thread(&context.http).await {
+            Ok(()) => {
+                tracing::info!("joined thread `{}`", channel_id);
+                self.joined_threads.write().await.insert(channel_id);
+            }
+            Err(err) => {
+                tracing::error!("couldn't join thread `{}` due to `{}`", channel_id, err);
+            }
+        }
+    }
+
+    /// Forget the archived or deleted thread.
+    pub async fn forget_thread(&self, channel_id: ChannelId) {
+        self.joined_threads.write().await.remove(&channel_id);
+        self.message_history
+            .write()
+            .await
+            .remove_channel(channel_id);
+    }
+
+    /// Replace the custom emojis of the guild with the ones fetched
+    /// from the API.
+    pub async fn sync_guild_emojis(&self, context: &Context, guild_id: GuildId) {
+        match guild_id.emojis(&context.http).await {
+            Ok(emojis) => {
+                self.emoji_index
+                    .write()
+                    .await
+                    .replace_guild(guild_id, emojis);
+            }
+            Err(err) => {
+                tracing::error!(
+                    "couldn't fetch emojis of the guild `{}` due to `{}`",
+                    guild_id,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Drop all the state of the guild the bot left, `channel_ids`
+    /// being the channels and threads of the guild.
+    pub async fn forget_guild(&self, guild_id: GuildId, channel_ids: &[ChannelId]) {
+        self.guild_settings.write().await.remove(&guild_id);
+        self.guild_policies.write().await.remove(&guild_id);
+        self.content_filters.write().await.remove(&guild_id);
+        self.guild_configs.write().await.remove(&guild_id);
+        self.command_toggles.write().await.remove(&guild_id);
+        self.emoji_index.write().await.remove_guild(guild_id);
+        self.audit_log.write().await.remove_guild(guild_id);
+        self.reaction_tracker.forget_guild(guild_id);
+        self.storage.forget_guild(guild_id);
+
+        let mut joined_threads = self.joined_threads.write().await;
+        let mut message_history = self.message_history.write().await;
+        channel_ids.iter().for_each(|channel_id| {
+            joined_threads.remove(channel_id);
+            message_history.remove_channel(*channel_id);
+        });
+    }
+
+    /// Fetch the recent messages of the channel from the API into the
+    /// message history, unless they were fetched before or the history
+    /// already goes back `count` messages.
+    pub async fn ensure_message_history(
+        &self,
+        context: &Context,
+        channel_id: ChannelId,
+        count: usize,
+    ) {
+        let limit = {
+            let message_history = self.message_history.read().await;
+            if !message_history.needs_fetch(channel_id, count) {
+                return;
+            }
+            message_history.max_messages_per_channel()
+        };
+
+        match channel_id
+            .messages(&context.http, |get_messages| {
+                get_messages.limit(limit as u64)
+            })
+            .await
+        {
+            Ok(messages) => {
+                tracing::info!(
+                    "fetched `{}` messages of channel `{}` into the message history",
+                    messages.len(),
+                    channel_id
+                );
+                self.message_history.write().await.insert_fetched(
+                    channel_id,
+                    &messages,
+                    context.cache.current_user_id(),
+                );
+            }
+            Err(err) => {
+                tracing::error!(
+                    "couldn't fetch messages of channel `{}` due to `{}`",
+                    channel_id,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Fetch the recent messages of the most recently active text
+    /// channels of the guild into the message history, skipping the
+    /// channels the bot cannot read the history of.
+    ///
+    /// Only the few channels with a message in the last day are
+    /// fetched so that starting in many guilds does not use up the
+    /// rate limit of the commands, the other channels are fetched by
+    /// [`BotContext::ensure_message_history()`] when needed.
+    pub async fn prewarm_message_history(&self, context: &Context, guild_id: GuildId) {
+        let bot_id = context.cache.current_user_id();
+        let oldest = Timestamp::now().unix_timestamp() - Self::PREWARM_MAX_AGE_SECS;
+        let mut channels = match context.cache.guild(guild_id) {
+            Some(guild) => guild
+                .channels
+                .values()
+                .filter_map(|channel| match channel {
+                    Channel::Guild(channel)
+                        if matches!(channel.kind, ChannelType::Text | ChannelType::News) =>
+                    {
+                        Some(channel)
+                    }
+                    _ => None,
+                })
+                .filter(|channel| {
+                    guild
+                        .members
+                        .get(&bot_id)
+                        .and_then(|member| guild.user_permissions_in(channel, member).ok())
+                        .is_some_and(|permissions| {
+                            permissions.view_channel() && permissions.read_message_history()
+                        })
+                })
+                .filter_map(|channel| Some((channel.last_message_id?, channel.id)))
+                .filter(|(last_message_id, _)| {
+                    last_message_id.created_at().unix_timestamp() >= oldest
+                })
+                .collect::<Vec<_>>(),
+            None => {
+                tracing::error!(
+                    "couldn't prewarm message history of guild `{}` since it is not cached",
+                    guild_id
+                );
+                return;
+            }
+        };
+
+        // Message IDs grow with time, so the most recently active
+        // channels come first.
+        channels.sort_unstable_by(|a, b| b.cmp(a));
+        for (_, channel_id) in channels.into_iter().take(Self::PREWARM_CHANNELS_PER_GUILD) {
+            self.ensure_message_history(context, channel_id, 1).await;
         }
     }
 }
 
+/// Must be used within a Tokio runtime, see [`BotContext::new()`].
 impl Default for BotContext {
     fn default() -> Self {
         Self::new()
     }
 }
-
-/// Bot added reactions.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct BotAddedReactions {
-    /// [`ChannelId`].
-    pub channel_id: ChannelId,
-    /// [`MessageId`].
-    pub message_id: MessageId,
-    /// [`UserId`].
-    pub user_id: UserId,
-    /// Set of [`ReactionType`]s for this particular message.
-    pub reaction_types: HashSet<ReactionType>,
-
-    /// Creation time of the emoji.
-    pub creation_time: std::time::Instant,
-}
@@ -0,0 +1,705 @@
+//! Emoji lookup, suggestions and resolution.
+
+use std::collections::{HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use serenity::model::prelude::*;
+
+use crate::commands::text_to_reactions::TEXT_TO_EMOJIS_EMOJI_NAME_TO_EMOJI;
+
+lazy_static! {
+    /// Unicode emoji shortcode (without the colons) to the emoji.
+    pub static ref UNICODE_EMOJI_SHORTCODES: HashMap<&'static str, &'static str> = [
+        ("grinning", "😀"),
+        ("smiley", "😃"),
+        ("smile", "😄"),
+        ("grin", "😁"),
+        ("laughing", "😆"),
+        ("sweat_smile", "😅"),
+        ("joy", "😂"),
+        ("rofl", "🤣"),
+        ("slight_smile", "🙂"),
+        ("upside_down", "🙃"),
+        ("wink", "😉"),
+        ("blush", "😊"),
+        ("innocent", "😇"),
+        ("heart_eyes", "😍"),
+        ("star_struck", "🤩"),
+        ("kissing_heart", "😘"),
+        ("yum", "😋"),
+        ("stuck_out_tongue", "😛"),
+        ("zany_face", "🤪"),
+        ("thinking", "🤔"),
+        ("shushing_face", "🤫"),
+        ("neutral_face", "😐"),
+        ("expressionless", "😑"),
+        ("no_mouth", "😶"),
+        ("smirk", "😏"),
+        ("unamused", "😒"),
+        ("rolling_eyes", "🙄"),
+        ("grimacing", "😬"),
+        ("relieved", "😌"),
+        ("pensive", "😔"),
+        ("sleepy", "😪"),
+        ("sleeping", "😴"),
+        ("mask", "😷"),
+        ("nauseated_face", "🤢"),
+        ("exploding_head", "🤯"),
+        ("cowboy", "🤠"),
+        ("partying_face", "🥳"),
+        ("sunglasses", "😎"),
+        ("nerd", "🤓"),
+        ("confused", "😕"),
+        ("worried", "😟"),
+        ("open_mouth", "😮"),
+        ("astonished", "😲"),
+        ("flushed", "😳"),
+        ("pleading_face", "🥺"),
+        ("fearful", "😨"),
+        ("cry", "😢"),
+        ("sob", "😭"),
+        ("scream", "😱"),
+        ("confounded", "😖"),
+        ("weary", "😩"),
+        ("tired_face", "😫"),
+        ("yawning_face", "🥱"),
+        ("triumph", "😤"),
+        ("rage", "😡"),
+        ("angry", "😠"),
+        ("skull", "💀"),
+        ("poop", "💩"),
+        ("clown", "🤡"),
+        ("ghost", "👻"),
+        ("alien", "👽"),
+        ("robot", "🤖"),
+        ("see_no_evil", "🙈"),
+        ("hear_no_evil", "🙉"),
+        ("speak_no_evil", "🙊"),
+        ("heart", "❤️"),
+        ("orange_heart", "🧡"),
+        ("yellow_heart", "💛"),
+        ("green_heart", "💚"),
+        ("blue_heart", "💙"),
+        ("purple_heart", "💜"),
+        ("black_heart", "🖤"),
+        ("white_heart", "🤍"),
+        ("broken_heart", "💔"),
+        ("sparkling_heart", "💖"),
+        ("100", "💯"),
+        ("anger", "💢"),
+        ("boom", "💥"),
+        ("dizzy", "💫"),
+        ("sweat_drops", "💦"),
+        ("zzz", "💤"),
+        ("wave", "👋"),
+        ("ok_hand", "👌"),
+        ("pinched_fingers", "🤌"),
+        ("v", "✌️"),
+        ("crossed_fingers", "🤞"),
+        ("metal", "🤘"),
+        ("call_me", "🤙"),
+        ("point_left", "👈"),
+        ("point_right", "👉"),
+        ("point_up", "☝️"),
+        ("point_down", "👇"),
+        ("thumbsup", "👍"),
+        ("thumbsdown", "👎"),
+        ("fist", "✊"),
+        ("punch", "👊"),
+        ("clap", "👏"),
+        ("raised_hands", "🙌"),
+        ("open_hands", "👐"),
+        ("handshake", "🤝"),
+        ("pray", "🙏"),
+        ("muscle", "💪"),
+        ("eyes", "👀"),
+        ("brain", "🧠"),
+        ("man_shrugging", "🤷‍♂️"),
+        ("woman_shrugging", "🤷‍♀️"),
+        ("person_facepalming", "🤦"),
+        ("dog", "🐶"),
+        ("cat", "🐱"),
+        ("mouse", "🐭"),
+        ("rabbit", "🐰"),
+        ("fox", "🦊"),
+        ("bear", "🐻"),
+        ("panda_face", "🐼"),
+        ("frog", "🐸"),
+        ("monkey_face", "🐵"),
+        ("chicken", "🐔"),
+        ("penguin", "🐧"),
+        ("duck", "🦆"),
+        ("owl", "🦉"),
+        ("unicorn", "🦄"),
+        ("bee", "🐝"),
+        ("snail", "🐌"),
+        ("butterfly", "🦋"),
+        ("turtle", "🐢"),
+        ("snake", "🐍"),
+        ("crab", "🦀"),
+        ("fish", "🐟"),
+        ("whale", "🐳"),
+        ("shark", "🦈"),
+        ("rose", "🌹"),
+        ("sunflower", "🌻"),
+        ("cactus", "🌵"),
+        ("evergreen_tree", "🌲"),
+        ("four_leaf_clover", "🍀"),
+        ("apple", "🍎"),
+        ("banana", "🍌"),
+        ("watermelon", "🍉"),
+        ("cherries", "🍒"),
+        ("avocado", "🥑"),
+        ("eggplant", "🍆"),
+        ("hot_pepper", "🌶️"),
+        ("bread", "🍞"),
+        ("cheese", "🧀"),
+        ("hamburger", "🍔"),
+        ("fries", "🍟"),
+        ("pizza", "🍕"),
+        ("taco", "🌮"),
+        ("popcorn", "🍿"),
+        ("cake", "🍰"),
+        ("cookie", "🍪"),
+        ("coffee", "☕"),
+        ("tea", "🍵"),
+        ("beer", "🍺"),
+        ("beers", "🍻"),
+        ("wine_glass", "🍷"),
+        ("champagne", "🍾"),
+        ("earth_africa", "🌍"),
+        ("rainbow", "🌈"),
+        ("sunny", "☀️"),
+        ("cloud", "☁️"),
+        ("zap", "⚡"),
+        ("snowflake", "❄️"),
+        ("fire", "🔥"),
+        ("droplet", "💧"),
+        ("star", "⭐"),
+        ("star2", "🌟"),
+        ("sparkles", "✨"),
+        ("crescent_moon", "🌙"),
+        ("rocket", "🚀"),
+        ("tada", "🎉"),
+        ("confetti_ball", "🎊"),
+        ("balloon", "🎈"),
+        ("gift", "🎁"),
+        ("trophy", "🏆"),
+        ("first_place", "🥇"),
+        ("medal", "🏅"),
+        ("soccer", "⚽"),
+        ("basketball", "🏀"),
+        ("video_game", "🎮"),
+        ("game_die", "🎲"),
+        ("dart", "🎯"),
+        ("musical_note", "🎵"),
+        ("notes", "🎶"),
+        ("microphone", "🎤"),
+        ("headphones", "🎧"),
+        ("guitar", "🎸"),
+        ("art", "🎨"),
+        ("camera", "📷"),
+        ("tv", "📺"),
+        ("computer", "💻"),
+        ("keyboard", "⌨️"),
+        ("bulb", "💡"),
+        ("books", "📚"),
+        ("pencil", "📝"),
+        ("memo", "📝"),
+        ("pushpin", "📌"),
+        ("paperclip", "📎"),
+        ("scissors", "✂️"),
+        ("lock", "🔒"),
+        ("key", "🔑"),
+        ("hammer", "🔨"),
+        ("wrench", "🔧"),
+        ("gear", "⚙️"),
+        ("bomb", "💣"),
+        ("moneybag", "💰"),
+        ("gem", "💎"),
+        ("bell", "🔔"),
+        ("hourglass", "⌛"),
+        ("alarm_clock", "⏰"),
+        ("white_check_mark", "✅"),
+        ("heavy_check_mark", "✔️"),
+        ("x", "❌"),
+        ("negative_squared_cross_mark", "❎"),
+        ("heavy_plus_sign", "➕"),
+        ("heavy_minus_sign", "➖"),
+        ("question", "❓"),
+        ("grey_question", "❔"),
+        ("exclamation", "❗"),
+        ("bangbang", "‼️"),
+        ("interrobang", "⁉️"),
+        ("warning", "⚠️"),
+        ("no_entry", "⛔"),
+        ("no_entry_sign", "🚫"),
+        ("recycle", "♻️"),
+        ("arrow_up", "⬆️"),
+        ("arrow_down", "⬇️"),
+        ("arrow_left", "⬅️"),
+        ("arrow_right", "➡️"),
+        ("arrows_counterclockwise", "🔄"),
+        ("red_circle", "🔴"),
+        ("orange_circle", "🟠"),
+        ("yellow_circle", "🟡"),
+        ("green_circle", "🟢"),
+        ("blue_circle", "🔵"),
+        ("purple_circle", "🟣"),
+        ("black_circle", "⚫"),
+        ("white_circle", "⚪"),
+        ("checkered_flag", "🏁"),
+        ("triangular_flag_on_post", "🚩"),
+        ("pirate_flag", "🏴‍☠️"),
+        ("rainbow_flag", "🏳️‍🌈"),
+        ("copyright", "©️"),
+        ("registered", "®️"),
+        ("tm", "™️"),
+        ("new", "🆕"),
+        ("free", "🆓"),
+        ("up", "🆙"),
+        ("cool", "🆒"),
+        ("ok", "🆗"),
+        ("sos", "🆘"),
+        ("ab", "🆎"),
+        ("cl", "🆑"),
+        ("id", "🆔"),
+        ("vs", "🆚"),
+    ]
+    .into_iter()
+    .chain(
+        TEXT_TO_EMOJIS_EMOJI_NAME_TO_EMOJI
+            .iter()
+            .map(|(name, emoji)| (name.trim_matches(':'), *emoji)),
+    )
+    .collect();
+}
+
+/// Maximum length of the name and value of an autocomplete choice.
+const AUTOCOMPLETE_CHOICE_MAX_LEN: usize = 100;
+
+/// Custom emojis of a guild, indexed by [`EmojiId`] and by name.
+///
+/// Multiple emojis can share a name, lookups by name prefer the
+/// available ones and then the oldest one.
+#[derive(Debug, Clone, Default)]
+pub struct GuildEmojiIndex {
+    /// [`EmojiId`] to its [`Emoji`].
+    emojis: HashMap<EmojiId, Emoji>,
+    /// Emoji name to the [`EmojiId`]s of that name, in order of
+    /// preference.
+    names: HashMap<String, Vec<EmojiId>>,
+}
+
+impl GuildEmojiIndex {
+    /// Create a new [`GuildEmojiIndex`] of the emojis.
+    pub fn new(emojis: impl IntoIterator<Item = Emoji>) -> Self {
+        let emojis = emojis
+            .into_iter()
+            .map(|emoji| (emoji.id, emoji))
+            .collect::<HashMap<_, _>>();
+
+        let mut names: HashMap<String, Vec<EmojiId>> = HashMap::new();
+        emojis.values().for_each(|emoji| {
+            names.entry(emoji.name.clone()).or_default().push(emoji.id);
+        });
+        names.values_mut().for_each(|emoji_ids| {
+            emoji_ids.sort_by_key(|emoji_id| (!emojis[emoji_id].available, *emoji_id));
+        });
+
+        Self { emojis, names }
+    }
+
+    /// [`Emoji`] of the [`EmojiId`].
+    pub fn get(&self, emoji_id: EmojiId) -> Option<&Emoji> {
+        self.emojis.get(&emoji_id)
+    }
+
+    /// Preferred [`Emoji`] of the name.
+    pub fn get_by_name(&self, name: &str) -> Option<&Emoji> {
+        self.all_by_name(name).next()
+    }
+
+    /// All the [`Emoji`]s of the name, in order of preference.
+    pub fn all_by_name<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a Emoji> + 'a {
+        self.names
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|emoji_id| &self.emojis[emoji_id])
+    }
+
+    /// All the [`Emoji`]s ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = &Emoji> {
+        let mut emojis = self.emojis.values().collect::<Vec<_>>();
+        emojis.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+        emojis.into_iter()
+    }
+
+    /// Number of emojis.
+    pub fn len(&self) -> usize {
+        self.emojis.len()
+    }
+
+    /// Check if there are no emojis.
+    pub fn is_empty(&self) -> bool {
+        self.emojis.is_empty()
+    }
+}
+
+/// Custom emojis of every guild the bot is in.
+#[derive(Debug, Clone, Default)]
+pub struct EmojiIndex {
+    /// [`GuildId`] to its [`GuildEmojiIndex`].
+    guilds: HashMap<GuildId, GuildEmojiIndex>,
+}
+
+impl EmojiIndex {
+    /// Replace all the emojis of the guild.
+    pub fn replace_guild(&mut self, guild_id: GuildId, emojis: impl IntoIterator<Item = Emoji>) {
+        self.guilds.insert(guild_id, GuildEmojiIndex::new(emojis));
+    }
+
+    /// Remove all the emojis of the guild.
+    pub fn remove_guild(&mut self, guild_id: GuildId) {
+        self.guilds.remove(&guild_id);
+    }
+
+    /// [`GuildEmojiIndex`] of the guild.
+    pub fn guild(&self, guild_id: GuildId) -> Option<&GuildEmojiIndex> {
+        self.guilds.get(&guild_id)
+    }
+
+    /// [`GuildEmojiIndex`] of every guild.
+    pub fn guilds(&self) -> impl Iterator<Item = (GuildId, &GuildEmojiIndex)> {
+        self.guilds
+            .iter()
+            .map(|(guild_id, guild_emojis)| (*guild_id, guild_emojis))
+    }
+
+    /// Preferred [`Emoji`] of the name in the guild `guild_id`,
+    /// falling back to the emojis of the other guilds if
+    /// `other_guilds` is set.
+    pub fn get_by_name(
+        &self,
+        guild_id: Option<GuildId>,
+        name: &str,
+        other_guilds: bool,
+    ) -> Option<&Emoji> {
+        guild_id
+            .and_then(|guild_id| self.guild(guild_id))
+            .and_then(|guild_emojis| guild_emojis.get_by_name(name))
+            .or_else(|| {
+                if !other_guilds {
+                    return None;
+                }
+                let mut guild_ids = self
+                    .guilds
+                    .keys()
+                    .filter(|other_guild_id| Some(**other_guild_id) != guild_id)
+                    .collect::<Vec<_>>();
+                guild_ids.sort();
+                guild_ids
+                    .into_iter()
+                    .find_map(|guild_id| self.guilds[guild_id].get_by_name(name))
+            })
+    }
+}
+
+/// Emoji suggested for the token being typed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmojiSuggestion {
+    /// Name shown to the user.
+    pub name: String,
+    /// Emoji text to react with.
+    pub emoji: String,
+}
+
+/// Source of an [`EmojiSuggestion`], ordered by preference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EmojiSource {
+    /// Custom emoji of the guild the command is used in.
+    Guild,
+    /// Unicode emoji.
+    Unicode,
+    /// Custom emoji of another guild the bot is in.
+    CrossGuild,
+}
+
+/// Rank of `name` for the `query`, lower is better. [`None`] if the
+/// name does not match at all.
+///
+/// Exact matches are best, followed by prefix matches, matches at the
+/// start of a `_` separated word and finally any substring match.
+pub fn emoji_name_rank(query: &str, name: &str) -> Option<u8> {
+    let name = name.to_lowercase();
+    if name == query {
+        Some(0)
+    } else if name.starts_with(query) {
+        Some(1)
+    } else if name.split('_').skip(1).any(|word| word.starts_with(query)) {
+        Some(2)
+    } else if name.contains(query) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// Normalize a token typed by the user for matching against emoji
+/// names, `:thumb` becomes `thumb`.
+fn normalize_query(token: &str) -> String {
+    token
+        .strip_prefix("<a:")
+        .or_else(|| token.strip_prefix('<'))
+        .unwrap_or(token)
+        .trim_matches(':')
+        .to_lowercase()
+}
+
+/// Suggest emojis for the `token` being typed in the guild
+/// `guild_id`, best suggestions first.
+///
+/// Custom emojis of the guild, Unicode shortcodes and custom emojis
+/// of the other guilds in the [`EmojiIndex`] are considered.
+pub fn suggest_emojis(
+    token: &str,
+    guild_id: Option<GuildId>,
+    emoji_index: &EmojiIndex,
+    limit: usize,
+) -> Vec<EmojiSuggestion> {
+    let query = normalize_query(token);
+
+    let custom_emojis = emoji_index
+        .guilds()
+        .flat_map(|(emojis_guild_id, emojis)| {
+            let source = if Some(emojis_guild_id) == guild_id {
+                EmojiSource::Guild
+            } else {
+                EmojiSource::CrossGuild
+            };
+            emojis
+                .iter()
+                .filter(|emoji| emoji.available)
+                .map(move |emoji| (source, emoji.name.as_str(), emoji.to_string()))
+        })
+        // Emojis of other guilds are only suggested once the user
+        // started typing to not drown the guild's own emojis.
+        .filter(|(source, _, _)| *source == EmojiSource::Guild || !query.is_empty());
+    let unicode_emojis = UNICODE_EMOJI_SHORTCODES
+        .iter()
+        .map(|(name, emoji)| (EmojiSource::Unicode, *name, emoji.to_string()));
+
+    let mut suggestions = custom_emojis
+        .chain(unicode_emojis)
+        .filter_map(|(source, name, emoji)| {
+            let rank = if query.is_empty() {
+                if source == EmojiSource::Guild {
+                    0
+                } else {
+                    return None;
+                }
+            } else {
+                emoji_name_rank(&query, name)?
+            };
+            Some(((rank, source, name.len(), name.to_string()), emoji))
+        })
+        .collect::<Vec<_>>();
+
+    suggestions.sort();
+
+    let mut suggested = HashSet::new();
+    suggestions
+        .into_iter()
+        .filter(|(_, emoji)| suggested.insert(emoji.clone()))
+        .take(limit)
+        .map(|((_, source, _, name), emoji)| EmojiSuggestion {
+            name: match source {
+                EmojiSource::Guild => format!(":{}:", name),
+                EmojiSource::Unicode => format!("{} :{}:", emoji, name),
+                EmojiSource::CrossGuild => format!(":{}: (other server)", name),
+            },
+            emoji,
+        })
+        .collect()
+}
+
+/// Autocomplete choices, as `(name, value)`, for the whitespace
+/// separated emoji text typed so far. Only the last emoji is
+/// completed, the preceding ones are kept as they are.
+pub fn autocomplete_emoji_text(
+    emoji_text: &str,
+    guild_id: Option<GuildId>,
+    emoji_index: &EmojiIndex,
+    limit: usize,
+) -> Vec<(String, String)> {
+    let (preceding, token) = if emoji_text.ends_with(char::is_whitespace) {
+        (emoji_text.trim_end(), "")
+    } else {
+        emoji_text
+            .trim_end()
+            .rsplit_once(char::is_whitespace)
+            .unwrap_or(("", emoji_text.trim()))
+    };
+    let preceding = preceding.trim();
+    let prefix = if preceding.is_empty() {
+        String::new()
+    } else {
+        format!("{} ", preceding)
+    };
+
+    suggest_emojis(token, guild_id, emoji_index, limit)
+        .into_iter()
+        .map(|suggestion| {
+            (
+                format!("{}{}", prefix, suggestion.name),
+                format!("{}{}", prefix, suggestion.emoji),
+            )
+        })
+        .filter(|(name, value)| {
+            name.chars().count() <= AUTOCOMPLETE_CHOICE_MAX_LEN
+                && value.chars().count() <= AUTOCOMPLETE_CHOICE_MAX_LEN
+        })
+        .collect()
+}
+
+/// Resolve an emoji `token` of the form `:name:` to the custom emoji
+/// of the guild `guild_id`, a Unicode emoji or a custom emoji of
+/// another guild, in that order. Other tokens are returned as is.
+pub fn resolve_emoji(token: &str, guild_id: Option<GuildId>, emoji_index: &EmojiIndex) -> String {
+    let name = match token
+        .strip_prefix(':')
+        .and_then(|token| token.strip_suffix(':'))
+    {
+        Some(name) if !name.is_empty() => name,
+        _ => return token.to_string(),
+    };
+
+    emoji_index
+        .get_by_name(guild_id, name, false)
+        .map(|emoji| emoji.to_string())
+        .or_else(|| {
+            UNICODE_EMOJI_SHORTCODES
+                .get(name)
+                .map(|emoji| emoji.to_string())
+        })
+        .or_else(|| {
+            emoji_index
+                .get_by_name(guild_id, name, true)
+                .map(|emoji| emoji.to_string())
+        })
+        .unwrap_or_else(|| token.to_string())
+}
+
+/// Check if the reaction is a custom emoji from outside the guild
+/// `guild_id`, which requires the external emojis permission.
+pub fn is_external_emoji(
+    reaction_type: &ReactionType,
+    guild_id: Option<GuildId>,
+    emoji_index: &EmojiIndex,
+) -> bool {
+    match reaction_type {
+        ReactionType::Custom { id, .. } => guild_id
+            .and_then(|guild_id| emoji_index.guild(guild_id))
+            .is_none_or(|guild_emojis| guild_emojis.get(*id).is_none()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::{
+        json::{json, prelude::from_value},
+        model::prelude::*,
+    };
+
+    use super::{
+        autocomplete_emoji_text, emoji_name_rank, is_external_emoji, resolve_emoji, EmojiIndex,
+    };
+
+    fn emoji(id: u64, name: &str, available: bool) -> Emoji {
+        from_value(json!({
+            "id": id.to_string(),
+            "name": name,
+            "animated": false,
+            "available": available,
+            "managed": false,
+            "require_colons": true,
+            "roles": [],
+        }))
+        .unwrap()
+    }
+
+    /// Ranking of emoji names.
+    #[test]
+    fn emoji_name_rank_01() {
+        assert_eq!(emoji_name_rank("fire", "fire"), Some(0));
+        assert_eq!(emoji_name_rank("thumbs", "thumbsup"), Some(1));
+        assert_eq!(emoji_name_rank("circle", "red_circle"), Some(2));
+        assert_eq!(emoji_name_rank("irc", "red_circle"), Some(3));
+        assert_eq!(emoji_name_rank("xyz", "red_circle"), None);
+    }
+
+    /// Only the last emoji is completed.
+    #[test]
+    fn autocomplete_emoji_text_01() {
+        let choices = autocomplete_emoji_text("🔥 :thumbsu", None, &EmojiIndex::default(), 25);
+        assert_eq!(
+            choices.first().unwrap(),
+            &("🔥 👍 :thumbsup:".to_string(), "🔥 👍".to_string())
+        );
+    }
+
+    /// Unicode shortcodes are resolved, other tokens are kept.
+    #[test]
+    fn resolve_emoji_01() {
+        assert_eq!(resolve_emoji(":fire:", None, &EmojiIndex::default()), "🔥");
+        assert_eq!(resolve_emoji("🔥", None, &EmojiIndex::default()), "🔥");
+        assert_eq!(
+            resolve_emoji(":not_an_emoji:", None, &EmojiIndex::default()),
+            ":not_an_emoji:"
+        );
+    }
+
+    /// Emojis are replaced as a whole and duplicate names prefer the
+    /// available and then the oldest emoji.
+    #[test]
+    fn emoji_index_01() {
+        let mut emoji_index = EmojiIndex::default();
+        emoji_index.replace_guild(
+            GuildId(1),
+            [
+                emoji(3, "blob", true),
+                emoji(2, "blob", false),
+                emoji(4, "blob", true),
+            ],
+        );
+        emoji_index.replace_guild(GuildId(2), [emoji(5, "fire", true)]);
+
+        let guild_emojis = emoji_index.guild(GuildId(1)).unwrap();
+        assert_eq!(guild_emojis.len(), 3);
+        assert_eq!(guild_emojis.get_by_name("blob").unwrap().id, EmojiId(3));
+        assert_eq!(resolve_emoji(":blob:", None, &emoji_index), "<:blob:3>");
+        assert_eq!(
+            resolve_emoji(":fire:", Some(GuildId(2)), &emoji_index),
+            "<:fire:5>"
+        );
+        assert_eq!(
+            resolve_emoji(":fire:", Some(GuildId(1)), &emoji_index),
+            "🔥"
+        );
+        let fire = ReactionType::try_from("<:fire:5>").unwrap();
+        assert!(!is_external_emoji(&fire, Some(GuildId(2)), &emoji_index));
+        assert!(is_external_emoji(&fire, Some(GuildId(1)), &emoji_index));
+        assert!(!is_external_emoji(
+            &'🔥'.into(),
+            Some(GuildId(1)),
+            &emoji_index
+        ));
+
+        emoji_index.replace_guild(GuildId(1), [emoji(4, "renamed", true)]);
+        let guild_emojis = emoji_index.guild(GuildId(1)).unwrap();
+        assert!(guild_emojis.get_by_name("blob").is_none());
+        assert_eq!(guild_emojis.get(EmojiId(4)).unwrap().name, "renamed");
+    }
+}
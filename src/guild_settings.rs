@@ -0,0 +1,136 @@
+//! Per guild settings.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serenity::model::prelude::*;
+
+use crate::message_history::MessageHistoryEntry;
+
+/// Settings of a guild.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    /// Skip the messages of bots when picking the last message.
+    pub ignore_bots: bool,
+    /// Skip the messages sent by webhooks when picking the last
+    /// message.
+    pub ignore_webhooks: bool,
+    /// Skip the system messages, such as join notices and pins, when
+    /// picking the last message.
+    pub ignore_system_messages: bool,
+    /// Bots and webhooks whose messages are never skipped.
+    pub allowed_authors: HashSet<UserId>,
+}
+
+impl GuildSettings {
+    /// Check if the entry can be picked as the last message.
+    pub fn is_eligible(&self, entry: &MessageHistoryEntry) -> bool {
+        if self.ignore_system_messages && is_system_message(entry.kind) {
+            return false;
+        }
+
+        if self.allowed_authors.contains(&entry.author_id) {
+            return true;
+        }
+
+        !(self.ignore_bots && entry.author_is_bot
+            || self.ignore_webhooks && entry.webhook_id.is_some())
+    }
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        Self {
+            ignore_bots: true,
+            ignore_webhooks: true,
+            ignore_system_messages: true,
+            allowed_authors: HashSet::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for GuildSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        writeln!(f, "ignore bots: {}", on_off(self.ignore_bots))?;
+        writeln!(f, "ignore webhooks: {}", on_off(self.ignore_webhooks))?;
+        writeln!(
+            f,
+            "ignore system messages: {}",
+            on_off(self.ignore_system_messages)
+        )?;
+        if self.allowed_authors.is_empty() {
+            write!(f, "allowed authors: none")
+        } else {
+            let mut allowed_authors = self.allowed_authors.iter().collect::<Vec<_>>();
+            allowed_authors.sort();
+            write!(
+                f,
+                "allowed authors: {}",
+                allowed_authors
+                    .into_iter()
+                    .map(|author_id| format!("`{}`", author_id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    }
+}
+
+/// Check if the message type is a system message rather than
+/// something a user wrote.
+pub fn is_system_message(kind: MessageType) -> bool {
+    !matches!(
+        kind,
+        MessageType::Regular
+            | MessageType::InlineReply
+            | MessageType::ChatInputCommand
+            | MessageType::ContextMenuCommand
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::{prelude::*, Timestamp};
+
+    use super::GuildSettings;
+    use crate::message_history::MessageHistoryEntry;
+
+    fn entry(
+        author_id: u64,
+        author_is_bot: bool,
+        webhook_id: Option<u64>,
+        kind: MessageType,
+    ) -> MessageHistoryEntry {
+        MessageHistoryEntry {
+            message_id: MessageId(1),
+            author_id: UserId(author_id),
+            timestamp: Timestamp::now(),
+            content: String::new(),
+            author_is_bot,
+            webhook_id: webhook_id.map(WebhookId),
+            kind,
+        }
+    }
+
+    /// Bots, webhooks and system messages are skipped unless allowed.
+    #[test]
+    fn guild_settings_01() {
+        let mut guild_settings = GuildSettings::default();
+        assert!(guild_settings.is_eligible(&entry(1, false, None, MessageType::Regular)));
+        assert!(guild_settings.is_eligible(&entry(1, false, None, MessageType::InlineReply)));
+        assert!(!guild_settings.is_eligible(&entry(1, false, None, MessageType::MemberJoin)));
+        assert!(!guild_settings.is_eligible(&entry(2, true, None, MessageType::Regular)));
+        assert!(!guild_settings.is_eligible(&entry(3, true, Some(3), MessageType::Regular)));
+
+        guild_settings.allowed_authors.insert(UserId(2));
+        assert!(guild_settings.is_eligible(&entry(2, true, None, MessageType::Regular)));
+        assert!(!guild_settings.is_eligible(&entry(2, true, None, MessageType::PinsAdd)));
+
+        guild_settings.ignore_webhooks = false;
+        assert!(!guild_settings.is_eligible(&entry(3, true, Some(3), MessageType::Regular)));
+        guild_settings.ignore_bots = false;
+        assert!(guild_settings.is_eligible(&entry(3, true, Some(3), MessageType::Regular)));
+    }
+}
@@ -1,38 +1,347 @@
 //! Reactinator - Helper bot to react with any emoji.
 
+pub mod audit_log;
+pub mod command_toggles;
 pub mod commands;
+pub mod config;
+pub mod content_filter;
 pub mod context;
+pub mod emojis;
+pub mod guild_settings;
+pub mod message_history;
+pub mod message_target;
+pub mod permissions;
+pub mod policy;
+pub mod rate_limit;
+pub mod reaction_tracker;
+pub mod storage;
 
 pub use context::BotContext;
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
-use commands::Command;
+use command_toggles::{CommandToggles, TOGGLEABLE_COMMANDS};
+use commands::{schema::schema_hash, Command};
+use message_history::{MessageHistory, MessageHistoryEntry};
+use policy::PolicyError;
+use rate_limit::{RateLimitConfig, RateLimited, RateLimiter};
 use serenity::{
     async_trait,
-    builder::CreateApplicationCommand,
+    builder::{CreateApplicationCommand, CreateApplicationCommands},
+    json::Value,
     model::{
-        application::interaction::application_command::ApplicationCommandInteraction, prelude::*,
+        application::{
+            command::Command as ApplicationCommand,
+            interaction::{
+                application_command::ApplicationCommandInteraction,
+                autocomplete::AutocompleteInteraction, modal::ModalSubmitInteraction,
+            },
+        },
+        prelude::*,
     },
     prelude::*,
 };
+use storage::{Storage, StorageError};
+use tokio::sync::{broadcast, Semaphore};
 
 /// Event handler.
+#[derive(Clone)]
 pub struct Handler {
     /// [`GuildCommands`].
-    guild_commands: Arc<RwLock<HashMap<GuildId, GuildCommands>>>,
+    guild_commands: Arc<RwLock<HashMap<GuildId, Arc<GuildCommands>>>>,
 
     /// [`BotContext`].
     bot_context: BotContext,
+
+    /// Bounds the number of command registrations running at once.
+    registration_permits: Arc<Semaphore>,
+
+    /// Register the commands globally instead of per guild.
+    global_commands: bool,
+
+    /// Changes of the [`CommandToggles`] are being listened to, see
+    /// [`Handler::listen_to_command_toggles()`].
+    listening_to_command_toggles: Arc<AtomicBool>,
 }
 
 impl Handler {
+    /// Maximum number of command registrations running at once.
+    const MAX_CONCURRENT_REGISTRATIONS: usize = 4;
+
     /// Create a new [`Handler`].
+    ///
+    /// Must be called within a Tokio runtime, see
+    /// [`BotContext::new()`].
     pub fn new() -> Self {
+        Self::with_bot_context(BotContext::new())
+    }
+
+    /// Create a new [`Handler`] keeping the state in the storage so
+    /// that it survives restarts, loading the state already in it.
+    ///
+    /// Must be called within a Tokio runtime, see
+    /// [`BotContext::with_storage()`].
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Result<Self, StorageError> {
+        Ok(Self::with_bot_context(BotContext::with_storage(storage)?))
+    }
+
+    /// Create a new [`Handler`] with the [`BotContext`].
+    fn with_bot_context(bot_context: BotContext) -> Self {
         Self {
             guild_commands: Arc::new(RwLock::new(HashMap::new())),
-            bot_context: BotContext::new(),
+            bot_context,
+            registration_permits: Arc::new(Semaphore::new(Self::MAX_CONCURRENT_REGISTRATIONS)),
+            global_commands: false,
+            listening_to_command_toggles: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Register the commands globally instead of per guild. Global
+    /// commands are available in every guild at once but take a while
+    /// to propagate.
+    pub fn with_global_commands(mut self, global_commands: bool) -> Self {
+        self.global_commands = global_commands;
+        self
+    }
+
+    /// Keep the content of the messages in the [`MessageHistory`] so
+    /// that messages can be targeted by their content. Requires the
+    /// privileged message content intent.
+    pub fn with_message_content(mut self, message_content: bool) -> Self {
+        self.bot_context.message_history = Arc::new(RwLock::new(
+            MessageHistory::default().with_content(message_content),
+        ));
+        self
+    }
+
+    /// Rate limit the commands and reactions with the given
+    /// [`RateLimitConfig`] instead of the default one.
+    pub fn with_rate_limits(mut self, config: RateLimitConfig) -> Self {
+        self.bot_context.rate_limiter = Arc::new(Mutex::new(RateLimiter::new(config)));
+        self
+    }
+
+    /// [`GuildCommands`] of the guild.
+    ///
+    /// The lock is only held to clone them, so that the interactions
+    /// with the commands run concurrently.
+    async fn guild_commands(&self, guild_id: GuildId) -> Option<Arc<GuildCommands>> {
+        self.guild_commands.read().await.get(&guild_id).cloned()
+    }
+
+    /// Build the commands enabled by the [`CommandToggles`], returning
+    /// the schema to register and the [`GuildCommands`] handling them.
+    fn build_commands(
+        &self,
+        command_toggles: &CommandToggles,
+    ) -> (CreateApplicationCommands, GuildCommands) {
+        fn register_command<C: Command>(
+            create_commands: &mut CreateApplicationCommands,
+            guild_commands: &mut GuildCommands,
+            bot_context: &BotContext,
+            command_toggles: &CommandToggles,
+        ) {
+            let mut create_application_command = CreateApplicationCommand::default();
+            let command = C::register(&mut create_application_command, bot_context);
+            let name = create_application_command
+                .0
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if command_toggles.is_enabled(name) {
+                guild_commands.insert(&create_application_command, command);
+                create_commands.add_application_command(create_application_command);
+            }
+        }
+
+        let mut create_commands = CreateApplicationCommands::default();
+        let mut guild_commands = GuildCommands::default();
+        register_command::<commands::ping::Ping>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::add_reaction::AddReaction>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::list_custom_emojis::ListCustomEmojis>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::reaction_history::ReactionHistory>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::text_to_reactions::TextToReactions>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::react_with::ReactWith>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::spell_on_message::SpellOnMessage>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::message_settings::MessageSettings>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::emoji::Emoji>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+        register_command::<commands::reactinator::Reactinator>(
+            &mut create_commands,
+            &mut guild_commands,
+            &self.bot_context,
+            command_toggles,
+        );
+
+        (create_commands, guild_commands)
+    }
+
+    /// Register the commands of the guild and fetch its emojis.
+    async fn setup_guild(&self, ctx: &Context, guild_id: GuildId) {
+        self.setup_guild_commands(ctx, guild_id).await;
+        self.bot_context.sync_guild_emojis(ctx, guild_id).await;
+    }
+
+    /// Build the commands enabled in the guild and register them.
+    ///
+    /// In the global command mode the commands registered for the
+    /// guild are cleared instead, every command is registered globally
+    /// and the ones disabled in the guild refuse to run.
+    async fn setup_guild_commands(&self, ctx: &Context, guild_id: GuildId) {
+        let command_toggles = self.bot_context.get_command_toggles(guild_id).await;
+        let (create_commands, guild_commands) = self.build_commands(&command_toggles);
+        self.guild_commands
+            .write()
+            .await
+            .insert(guild_id, Arc::new(guild_commands));
+
+        let schema = if self.global_commands {
+            Vec::new()
+        } else {
+            create_commands.0
+        };
+        self.sync_commands(ctx, Some(guild_id), schema).await;
+    }
+
+    /// Set up the commands of the guilds again whenever their
+    /// [`CommandToggles`] change, only the first call does anything.
+    fn listen_to_command_toggles(&self, ctx: &Context) {
+        if self
+            .listening_to_command_toggles
+            .swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+
+        let handler = self.clone();
+        let ctx = ctx.clone();
+        let mut command_toggle_events = self.bot_context.command_toggle_events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match command_toggle_events.recv().await {
+                    Ok(guild_id) => {
+                        tracing::info!("commands of guild `{}` changed", guild_id);
+                        handler.setup_guild_commands(&ctx, guild_id).await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::error!("missed {} changes of the enabled commands", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Register the commands of the `schema` for the guild, or
+    /// globally if [`None`], unless they are already registered.
+    async fn sync_commands(&self, ctx: &Context, guild_id: Option<GuildId>, schema: Vec<Value>) {
+        let scope = guild_id.map_or_else(
+            || "global scope".to_string(),
+            |guild_id| format!("guild `{}`", guild_id),
+        );
+
+        let _permit = self
+            .registration_permits
+            .acquire()
+            .await
+            .expect("registration semaphore is never closed");
+
+        let registered = match guild_id {
+            Some(guild_id) => guild_id.get_application_commands(&ctx.http).await,
+            None => ApplicationCommand::get_global_application_commands(&ctx.http).await,
+        };
+        match registered {
+            Ok(registered) => {
+                let registered = registered
+                    .iter()
+                    .filter_map(|command| serenity::json::prelude::to_value(command).ok())
+                    .collect::<Vec<_>>();
+                if schema_hash(&registered) == schema_hash(&schema) {
+                    tracing::info!("commands of the {} are up to date", scope);
+                    return;
+                }
+            }
+            Err(err) => {
+                tracing::error!(
+                    "couldn't fetch the registered commands of the {} due to `{}`",
+                    scope,
+                    err
+                );
+            }
+        }
+
+        let commands = match guild_id {
+            Some(guild_id) => {
+                guild_id
+                    .set_application_commands(&ctx.http, |commands| {
+                        commands.0 = schema;
+                        commands
+                    })
+                    .await
+            }
+            None => {
+                ApplicationCommand::set_global_application_commands(&ctx.http, |commands| {
+                    commands.0 = schema;
+                    commands
+                })
+                .await
+            }
+        };
+
+        match commands {
+            Ok(commands) => {
+                tracing::info!("{} has the commands {:#?}", scope, commands);
+            }
+            Err(err) => {
+                tracing::error!("couldn't create commands of the {} due to `{}`", scope, err);
+            }
         }
     }
 }
@@ -60,21 +369,153 @@ impl GuildCommands {
         );
     }
 
-    /// Interaction with the commands.
+    /// Check that the command can run, the [`policy::GuildPolicy`] of
+    /// the guild allows it and none of the rate limits is hit.
+    async fn admit(
+        command_interaction: &ApplicationCommandInteraction,
+        context: &Context,
+        bot_context: &BotContext,
+    ) -> Result<(), Refusal> {
+        if let Some(member) = &command_interaction.member {
+            bot_context
+                .check_policy(
+                    &context.cache,
+                    &command_interaction.data.name,
+                    command_interaction.channel_id,
+                    member,
+                )
+                .await
+                .map_err(Refusal::Policy)?;
+        }
+
+        bot_context
+            .rate_limiter
+            .lock()
+            .await
+            .check_command(
+                command_interaction.user.id,
+                command_interaction.channel_id,
+                command_interaction.guild_id,
+                Instant::now(),
+            )
+            .map_err(Refusal::RateLimited)
+    }
+
+    /// Interaction with the commands, unless [`Self::admit()`] refuses
+    /// it.
     pub async fn interaction(
-        &mut self,
+        &self,
         command_interaction: &ApplicationCommandInteraction,
         context: &Context,
         bot_context: &BotContext,
     ) {
-        match self.0.get_mut(&command_interaction.data.name) {
+        let command = match self.0.get(&command_interaction.data.name) {
+            Some(command) => command,
+            None => {
+                // Global commands are registered in every guild, even
+                // the ones that disabled them.
+                let name = &command_interaction.data.name;
+                let content = if TOGGLEABLE_COMMANDS
+                    .iter()
+                    .any(|(toggleable, _)| toggleable == name)
+                {
+                    tracing::info!(
+                        "user `{}` - {} - disabled in this server",
+                        command_interaction.user.tag(),
+                        name
+                    );
+                    "error: command is disabled in this server"
+                } else {
+                    tracing::error!("unknown command {}", name);
+                    "error: unknown command"
+                };
+                if let Err(err) = command_interaction
+                    .create_interaction_response(&context.http, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|message| {
+                                message.content(content).ephemeral(true)
+                            })
+                    })
+                    .await
+                {
+                    tracing::error!(
+                        "couldn't respond to slash command for user `{}` due to `{}`",
+                        command_interaction.user.tag(),
+                        err,
+                    );
+                }
+                return;
+            }
+        };
+
+        if let Err(err) = Self::admit(command_interaction, context, bot_context).await {
+            tracing::info!(
+                "user `{}` - {} - {}",
+                command_interaction.user.tag(),
+                command_interaction.data.name,
+                err
+            );
+            if let Err(err) = command_interaction
+                .create_interaction_response(&context.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            message.content(format!("error: {}", err)).ephemeral(true)
+                        })
+                })
+                .await
+            {
+                tracing::error!(
+                    "couldn't respond to slash command for user `{}` due to `{}`",
+                    command_interaction.user.tag(),
+                    err,
+                );
+            }
+            return;
+        }
+
+        command
+            .interaction(command_interaction, context, bot_context)
+            .await;
+    }
+
+    /// Autocompletion for the command.
+    pub async fn autocomplete(
+        &self,
+        autocomplete_interaction: &AutocompleteInteraction,
+        context: &Context,
+        bot_context: &BotContext,
+    ) {
+        match self.0.get(&autocomplete_interaction.data.name) {
+            Some(command) => {
+                command
+                    .autocomplete(autocomplete_interaction, context, bot_context)
+                    .await;
+            }
+            None => {
+                tracing::error!("unknown command {}", autocomplete_interaction.data.name);
+            }
+        }
+    }
+
+    /// Modal submission to the command that opened the modal.
+    pub async fn modal_submit(
+        &self,
+        modal_interaction: &ModalSubmitInteraction,
+        context: &Context,
+        bot_context: &BotContext,
+    ) {
+        match commands::split_modal_custom_id(&modal_interaction.data.custom_id)
+            .and_then(|(command_name, _)| self.0.get(command_name))
+        {
             Some(command) => {
                 command
-                    .interaction(command_interaction, context, bot_context)
+                    .modal_submit(modal_interaction, context, bot_context)
                     .await;
             }
             None => {
-                tracing::error!("unknown command {}", command_interaction.data.name);
+                tracing::error!("no command for modal {}", modal_interaction.data.custom_id);
             }
         }
     }
@@ -86,26 +527,120 @@ impl Default for GuildCommands {
     }
 }
 
+/// Reason [`GuildCommands::admit()`] refuses a command.
+#[derive(Debug)]
+enum Refusal {
+    Policy(PolicyError),
+    RateLimited(RateLimited),
+}
+
+impl std::fmt::Display for Refusal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Refusal::Policy(err) => write!(f, "{}", err),
+            Refusal::RateLimited(err) => write!(f, "{}", err),
+        }
+    }
+}
+
 #[async_trait]
 impl EventHandler for Handler {
     async fn message(&self, context: Context, message: Message) {
-        if message.author.id != context.cache.current_user_id() {
+        if message_history::is_tracked(&message, context.cache.current_user_id()) {
+            self.bot_context
+                .message_history
+                .write()
+                .await
+                .insert(message.channel_id, MessageHistoryEntry::from(&message));
+        }
+    }
+
+    async fn message_update(
+        &self,
+        _context: Context,
+        _old_if_available: Option<Message>,
+        _new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        if let Some(content) = &event.content {
             self.bot_context
-                .last_message_ids
+                .message_history
                 .write()
                 .await
-                .insert(message.channel_id, message.id);
+                .update_content(event.channel_id, event.id, content);
+        }
+    }
+
+    async fn message_delete(
+        &self,
+        _context: Context,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+        self.bot_context
+            .message_history
+            .write()
+            .await
+            .remove(channel_id, &[deleted_message_id]);
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        _context: Context,
+        channel_id: ChannelId,
+        deleted_message_ids: Vec<MessageId>,
+        _guild_id: Option<GuildId>,
+    ) {
+        self.bot_context
+            .message_history
+            .write()
+            .await
+            .remove(channel_id, &deleted_message_ids);
+    }
+
+    async fn thread_create(&self, context: Context, thread: GuildChannel) {
+        // The starter post of a forum thread is sent within the thread
+        // before the bot can see it, so fetch it.
+        let is_forum_post = thread
+            .parent_id
+            .and_then(|parent_id| context.cache.guild_channel(parent_id))
+            .is_some_and(|parent| parent.kind == ChannelType::Forum);
+        if is_forum_post {
+            self.bot_context
+                .ensure_message_history(&context, thread.id, 1)
+                .await;
+        }
+    }
+
+    async fn thread_update(&self, _context: Context, thread: GuildChannel) {
+        if thread
+            .thread_metadata
+            .is_some_and(|thread_metadata| thread_metadata.archived)
+        {
+            self.bot_context.forget_thread(thread.id).await;
         }
     }
 
+    async fn thread_delete(&self, _context: Context, thread: PartialGuildChannel) {
+        self.bot_context.forget_thread(thread.id).await;
+    }
+
+    async fn channel_delete(&self, _context: Context, channel: &GuildChannel) {
+        self.bot_context
+            .message_history
+            .write()
+            .await
+            .remove_channel(channel.id);
+    }
+
     async fn interaction_create(&self, context: Context, interaction: Interaction) {
-        if let Interaction::ApplicationCommand(command_interaction) = interaction {
-            tracing::info!("command interaction: {:#?}", command_interaction);
+        match interaction {
+            Interaction::ApplicationCommand(command_interaction) => {
+                tracing::info!("command interaction: {:#?}", command_interaction);
 
-            match &command_interaction.guild_id {
-                Some(guild_id) => {
-                    let mut guild_commands = self.guild_commands.write().await;
-                    match guild_commands.get_mut(guild_id) {
+                match &command_interaction.guild_id {
+                    Some(guild_id) => match self.guild_commands(*guild_id).await {
                         Some(guild_commands) => {
                             guild_commands
                                 .interaction(&command_interaction, &context, &self.bot_context)
@@ -114,98 +649,133 @@ impl EventHandler for Handler {
                         None => {
                             tracing::error!("commands not built for guild id {}", guild_id);
                         }
+                    },
+                    None => {
+                        tracing::error!("expected guild id");
                     }
                 }
-                None => {
-                    tracing::error!("expected guild id");
+            }
+            Interaction::Autocomplete(autocomplete_interaction) => {
+                tracing::debug!("autocomplete interaction: {:#?}", autocomplete_interaction);
+
+                match &autocomplete_interaction.guild_id {
+                    Some(guild_id) => match self.guild_commands(*guild_id).await {
+                        Some(guild_commands) => {
+                            guild_commands
+                                .autocomplete(
+                                    &autocomplete_interaction,
+                                    &context,
+                                    &self.bot_context,
+                                )
+                                .await
+                        }
+                        None => {
+                            tracing::error!("commands not built for guild id {}", guild_id);
+                        }
+                    },
+                    None => {
+                        tracing::error!("expected guild id");
+                    }
                 }
             }
+            Interaction::ModalSubmit(modal_interaction) => {
+                tracing::info!("modal submit interaction: {:#?}", modal_interaction);
+
+                match &modal_interaction.guild_id {
+                    Some(guild_id) => match self.guild_commands(*guild_id).await {
+                        Some(guild_commands) => {
+                            guild_commands
+                                .modal_submit(&modal_interaction, &context, &self.bot_context)
+                                .await
+                        }
+                        None => {
+                            tracing::error!("commands not built for guild id {}", guild_id);
+                        }
+                    },
+                    None => {
+                        tracing::error!("expected guild id");
+                    }
+                }
+            }
+            _ => {}
         }
     }
 
-    async fn ready(&self, ctx: Context, ready: Ready) {
+    async fn cache_ready(&self, context: Context, guild_ids: Vec<GuildId>) {
+        let bot_context = self.bot_context.clone();
+        tokio::spawn(async move {
+            for guild_id in guild_ids {
+                bot_context
+                    .prewarm_message_history(&context, guild_id)
+                    .await;
+            }
+        });
+    }
+
+    async fn ready(&self, context: Context, ready: Ready) {
         tracing::info!("{} connected", ready.user.name);
 
-        for guild_id in ctx.cache.guilds().into_iter() {
-            let mut guild_commands = self.guild_commands.write().await;
-            let commands = guild_id
-                .set_application_commands(&ctx.http, |commands| {
-                    let guild_commands = guild_commands
-                        .entry(guild_id)
-                        .or_insert_with(GuildCommands::default);
-
-                    fn register_command<'a, C: Command>(
-                        create_application_command: &'a mut CreateApplicationCommand,
-                        guild_commands: &mut GuildCommands,
-                        bot_context: &BotContext,
-                    ) -> &'a mut CreateApplicationCommand {
-                        let command = C::register(create_application_command, bot_context);
-                        guild_commands.insert(&create_application_command, command);
-                        create_application_command
-                    }
+        // Reactions left over from before a restart are no longer
+        // tracked, remove them.
+        self.bot_context
+            .reaction_tracker
+            .remove_stale_reactions(&context);
+        self.listen_to_command_toggles(&context);
 
-                    commands
-                        // .create_application_command(|create_application_command| {
-                        //     register_command::<commands::ping::Ping>(
-                        //         create_application_command,
-                        //         guild_commands,
-                        //         &self.bot_context,
-                        //     )
-                        // })
-                        .create_application_command(|create_application_command| {
-                            register_command::<commands::add_reaction::AddReaction>(
-                                create_application_command,
-                                guild_commands,
-                                &self.bot_context,
-                            )
-                        })
-                        .create_application_command(|create_application_command| {
-                            register_command::<commands::list_custom_emojis::ListCustomEmojis>(
-                                create_application_command,
-                                guild_commands,
-                                &self.bot_context,
-                            )
-                        })
-                        .create_application_command(|create_application_command| {
-                            register_command::<commands::text_to_reactions::TextToReactions>(
-                                create_application_command,
-                                guild_commands,
-                                &self.bot_context,
-                            )
-                        })
-                })
-                .await;
+        if self.global_commands {
+            let (create_commands, _) = self.build_commands(&CommandToggles::all_enabled());
+            self.sync_commands(&context, None, create_commands.0).await;
+        }
+    }
 
-            match commands {
-                Ok(commands) => {
-                    tracing::info!("guild `{}` has the commands {:#?}", guild_id, commands);
-                }
-                Err(err) => {
-                    tracing::error!("couldn't create commands due to `{}`", err);
-                }
-            }
+    async fn guild_create(&self, context: Context, guild: Guild, is_new: bool) {
+        // Guilds are also created on every connection, only set up the
+        // ones not set up yet.
+        if self.guild_commands.read().await.contains_key(&guild.id) {
+            return;
+        }
 
-            match guild_id.emojis(&ctx.http).await {
-                Ok(emojis) => {
-                    self.bot_context
-                        .guild_emojis
-                        .write()
-                        .await
-                        .entry(guild_id)
-                        .or_insert_with(HashMap::new)
-                        .extend(emojis.into_iter().map(|emoji| (emoji.name.clone(), emoji)));
-                }
-                Err(err) => {
-                    tracing::error!(
-                        "couldn't fetch emojis of the guild `{}` due to `{}`",
-                        guild_id,
-                        err
-                    );
-                }
-            }
+        self.setup_guild(&context, guild.id).await;
+
+        if is_new {
+            tracing::info!("joined guild `{}`", guild.id);
+            let bot_context = self.bot_context.clone();
+            tokio::spawn(async move {
+                bot_context
+                    .prewarm_message_history(&context, guild.id)
+                    .await;
+            });
         }
     }
 
+    async fn guild_delete(
+        &self,
+        _context: Context,
+        incomplete: UnavailableGuild,
+        full: Option<Guild>,
+    ) {
+        // Unavailable guilds are only out of reach during an outage.
+        if incomplete.unavailable {
+            return;
+        }
+
+        tracing::info!("left guild `{}`", incomplete.id);
+        self.guild_commands.write().await.remove(&incomplete.id);
+
+        let channel_ids = full
+            .map(|guild| {
+                guild
+                    .channels
+                    .into_keys()
+                    .chain(guild.threads.into_iter().map(|thread| thread.id))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        self.bot_context
+            .forget_guild(incomplete.id, &channel_ids)
+            .await;
+    }
+
     async fn guild_emojis_update(
         &self,
         _context: Context,
@@ -213,99 +783,96 @@ impl EventHandler for Handler {
         emojis: HashMap<EmojiId, Emoji>,
     ) {
         self.bot_context
-            .guild_emojis
+            .emoji_index
             .write()
             .await
-            .entry(guild_id)
-            .or_insert_with(HashMap::new)
-            .extend(
-                emojis
-                    .into_iter()
-                    .map(|(_, emoji)| (emoji.name.clone(), emoji)),
-            );
+            .replace_guild(guild_id, emojis.into_values());
+    }
+
+    async fn resume(&self, context: Context, _resumed: ResumedEvent) {
+        // Emoji updates may have been missed while disconnected.
+        let bot_context = self.bot_context.clone();
+        tokio::spawn(async move {
+            for guild_id in context.cache.guilds() {
+                bot_context.sync_guild_emojis(&context, guild_id).await;
+            }
+        });
     }
 
     async fn reaction_add(&self, context: Context, reaction: Reaction) {
-        if let (Some(guild_id), Some(user_id)) = (&reaction.guild_id, reaction.user_id) {
-            let guilds_to_bot_added_reactions = self.bot_context.bot_added_reactions.read().await;
-            if let Some(bot_added_reactions) = guilds_to_bot_added_reactions.get(guild_id) {
-                if let Some(bot_added_reactions_index) = bot_added_reactions
-                    .iter()
-                    .enumerate()
-                    .find_map(|(bot_added_reactions_index, bot_added_reactions)| {
-                        let bot_added_reactions = bot_added_reactions.read().unwrap();
-                        (bot_added_reactions.channel_id == reaction.channel_id
-                            && bot_added_reactions.message_id == reaction.message_id
-                            && bot_added_reactions.user_id == user_id
-                            && bot_added_reactions.reaction_types.contains(&reaction.emoji))
-                        .then_some(bot_added_reactions_index)
-                    })
-                {
-                    drop(guilds_to_bot_added_reactions);
-                    let mut guilds_to_bot_added_reactions =
-                        self.bot_context.bot_added_reactions.write().await;
-                    let bot_added_reactions = guilds_to_bot_added_reactions
-                        .get_mut(guild_id)
-                        .unwrap()
-                        .get_mut(bot_added_reactions_index)
-                        .unwrap();
-                    match reaction.message(&context.http).await {
-                        Ok(message) => {
-                            bot_added_reactions
-                                .write()
-                                .unwrap()
-                                .reaction_types
-                                .remove(&reaction.emoji);
-                            match message
-                                .delete_reaction(
-                                    &context.http,
-                                    Some(context.cache.current_user_id()),
-                                    reaction.emoji.clone(),
-                                )
-                                .await
-                            {
-                                Ok(_) => {
-                                    tracing::info!(
-                                        "deleted reaction `{}` from `{}` since user reacted",
-                                        reaction.emoji,
-                                        reaction.message_id,
-                                    );
-                                }
-                                Err(err) => {
-                                    tracing::error!(
-                                        "unable to delete reaction `{:?}` due to `{}`",
-                                        reaction,
-                                        err
-                                    );
-                                }
-                            }
-                        }
-                        Err(err) => {
-                            tracing::error!(
-                                "couldn't get message for reaction `{:?}` due to `{}`",
-                                reaction,
-                                err
-                            );
-                        }
-                    }
+        if !self
+            .bot_context
+            .reaction_tracker
+            .reaction_added(&reaction)
+            .await
+        {
+            return;
+        }
 
-                    if bot_added_reactions
-                        .read()
-                        .unwrap()
-                        .reaction_types
-                        .is_empty()
-                    {
-                        tracing::info!(
-                            "removing bot added reactions at index `{}` since it is now empty",
-                            bot_added_reactions_index
-                        );
-                        guilds_to_bot_added_reactions
-                            .get_mut(guild_id)
-                            .unwrap()
-                            .swap_remove(bot_added_reactions_index);
-                    }
-                }
+        match context
+            .http
+            .delete_reaction(
+                reaction.channel_id.0,
+                reaction.message_id.0,
+                Some(context.cache.current_user_id().0),
+                &reaction.emoji,
+            )
+            .await
+        {
+            Ok(_) => {
+                tracing::info!(
+                    "deleted reaction `{}` from `{}` since user reacted",
+                    reaction.emoji,
+                    reaction.message_id,
+                );
+            }
+            Err(err) => {
+                tracing::error!(
+                    "unable to delete reaction `{:?}` due to `{}`",
+                    reaction,
+                    err
+                );
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serenity::json::Value;
+
+    use super::Handler;
+    use crate::command_toggles::{CommandToggles, TOGGLEABLE_COMMANDS};
+
+    /// Every command but `reactinator` can be toggled, and only the
+    /// enabled ones are built.
+    #[tokio::test]
+    async fn handler_01() {
+        let handler = Handler::new();
+        let mut command_toggles = CommandToggles::default();
+        TOGGLEABLE_COMMANDS
+            .iter()
+            .for_each(|(name, _)| command_toggles.set(name, true).unwrap());
+        let (create_commands, guild_commands) = handler.build_commands(&command_toggles);
+        let mut names = create_commands
+            .0
+            .iter()
+            .filter_map(|command| command.get("name").and_then(Value::as_str))
+            .collect::<Vec<_>>();
+        names.sort();
+        let mut expected = TOGGLEABLE_COMMANDS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(["reactinator"])
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(names, expected);
+        assert_eq!(guild_commands.0.len(), expected.len());
+
+        let (create_commands, _) = handler.build_commands(&CommandToggles::default());
+        assert!(!create_commands
+            .0
+            .iter()
+            .any(|command| command.get("name").and_then(Value::as_str) == Some("ping")));
+    }
+}
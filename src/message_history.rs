@@ -0,0 +1,565 @@
+//! Recent message history of the channels.
+
+use std::collections::{HashMap, VecDeque};
+
+use serenity::model::{prelude::*, Timestamp};
+
+use crate::guild_settings::GuildSettings;
+
+/// Entry of the [`ChannelMessageHistory`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageHistoryEntry {
+    /// [`MessageId`].
+    pub message_id: MessageId,
+    /// [`UserId`] of the author of the message.
+    pub author_id: UserId,
+    /// Time the message was sent.
+    pub timestamp: Timestamp,
+    /// Content of the message, empty unless the history keeps the
+    /// content, see [`MessageHistory::with_content()`].
+    pub content: String,
+    /// Author of the message is a bot.
+    pub author_is_bot: bool,
+    /// [`WebhookId`] of the webhook that sent the message.
+    pub webhook_id: Option<WebhookId>,
+    /// [`MessageType`] of the message.
+    pub kind: MessageType,
+}
+
+impl From<&Message> for MessageHistoryEntry {
+    fn from(message: &Message) -> Self {
+        Self {
+            message_id: message.id,
+            author_id: message.author.id,
+            timestamp: message.timestamp,
+            content: message.content.clone(),
+            author_is_bot: message.author.bot,
+            webhook_id: message.webhook_id,
+            kind: message.kind,
+        }
+    }
+}
+
+/// Filter on the entries of the [`ChannelMessageHistory`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageFilter {
+    /// Only messages by this author.
+    pub author_id: Option<UserId>,
+    /// Only messages containing this text, compared case
+    /// insensitively.
+    pub contains: Option<String>,
+    /// Only messages eligible under the [`GuildSettings`]. Not applied
+    /// when the author is given explicitly.
+    pub guild_settings: Option<GuildSettings>,
+}
+
+impl MessageFilter {
+    /// Create a new [`MessageFilter`].
+    pub fn new(author_id: Option<UserId>, contains: Option<&str>) -> Self {
+        Self {
+            author_id,
+            contains: contains
+                .map(|contains| contains.trim().to_lowercase())
+                .filter(|contains| !contains.is_empty()),
+            guild_settings: None,
+        }
+    }
+
+    /// Only match the messages eligible under the [`GuildSettings`],
+    /// unless the author is given explicitly.
+    pub fn with_guild_settings(mut self, guild_settings: GuildSettings) -> Self {
+        if self.author_id.is_none() {
+            self.guild_settings = Some(guild_settings);
+        }
+        self
+    }
+
+    /// Check if an author or a content to match is given.
+    pub fn has_criteria(&self) -> bool {
+        self.author_id.is_some() || self.contains.is_some()
+    }
+
+    /// Check if the entry matches the filter.
+    pub fn matches(&self, entry: &MessageHistoryEntry) -> bool {
+        self.guild_settings
+            .as_ref()
+            .is_none_or(|guild_settings| guild_settings.is_eligible(entry))
+            && self
+                .author_id
+                .is_none_or(|author_id| entry.author_id == author_id)
+            && self
+                .contains
+                .as_ref()
+                .is_none_or(|contains| entry.content.to_lowercase().contains(contains.as_str()))
+    }
+}
+
+/// Check if the message should be tracked in the [`MessageHistory`].
+///
+/// Messages of the bot itself are never tracked.
+pub fn is_tracked(message: &Message, bot_id: UserId) -> bool {
+    message.author.id != bot_id
+}
+
+/// Bounded history of the recent messages of a channel.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelMessageHistory {
+    /// Entries ordered from oldest to newest.
+    entries: VecDeque<MessageHistoryEntry>,
+    /// Latest entry of each author, kept beyond the newest entries so
+    /// that quiet authors can still be targeted.
+    latest_by_author: HashMap<UserId, MessageHistoryEntry>,
+    /// Recent messages of the channel were fetched from the API.
+    fetched: bool,
+}
+
+impl ChannelMessageHistory {
+    /// Maximum number of authors whose latest entry is kept.
+    pub const MAX_AUTHORS: usize = 200;
+
+    /// Insert the entry, keeping at most `max_messages` of the newest
+    /// entries. Entries already present are ignored.
+    fn insert(&mut self, entry: MessageHistoryEntry, max_messages: usize) {
+        match self.latest_by_author.get(&entry.author_id) {
+            Some(latest) if latest.message_id >= entry.message_id => {}
+            _ => {
+                self.latest_by_author.insert(entry.author_id, entry.clone());
+            }
+        }
+        while self.latest_by_author.len() > Self::MAX_AUTHORS {
+            match self
+                .latest_by_author
+                .values()
+                .min_by_key(|entry| entry.message_id)
+                .map(|entry| entry.author_id)
+            {
+                Some(least_recent_author_id) => {
+                    self.latest_by_author.remove(&least_recent_author_id);
+                }
+                None => break,
+            }
+        }
+
+        match self
+            .entries
+            .binary_search_by_key(&entry.message_id, |entry| entry.message_id)
+        {
+            Ok(_) => {}
+            Err(index) => self.entries.insert(index, entry),
+        }
+
+        while self.entries.len() > max_messages {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Remove the entry of the message, returning if it was present.
+    ///
+    /// If it was the latest entry of its author, the previous entry of
+    /// the author takes its place when known.
+    fn remove(&mut self, message_id: MessageId) -> bool {
+        let removed = match self
+            .entries
+            .binary_search_by_key(&message_id, |entry| entry.message_id)
+        {
+            Ok(index) => self.entries.remove(index).is_some(),
+            Err(_) => false,
+        };
+
+        let author_id = self
+            .latest_by_author
+            .values()
+            .find(|entry| entry.message_id == message_id)
+            .map(|entry| entry.author_id);
+        if let Some(author_id) = author_id {
+            match self
+                .entries
+                .iter()
+                .rev()
+                .find(|entry| entry.author_id == author_id)
+            {
+                Some(previous) => {
+                    self.latest_by_author.insert(author_id, previous.clone());
+                }
+                None => {
+                    self.latest_by_author.remove(&author_id);
+                }
+            }
+            return true;
+        }
+
+        removed
+    }
+
+    /// Update the content of the entry of the message.
+    fn update_content(&mut self, message_id: MessageId, content: &str) {
+        if let Ok(index) = self
+            .entries
+            .binary_search_by_key(&message_id, |entry| entry.message_id)
+        {
+            self.entries[index].content = content.to_string();
+        }
+        if let Some(entry) = self
+            .latest_by_author
+            .values_mut()
+            .find(|entry| entry.message_id == message_id)
+        {
+            entry.content = content.to_string();
+        }
+    }
+
+    /// Latest entry.
+    pub fn latest(&self) -> Option<&MessageHistoryEntry> {
+        self.entries.back()
+    }
+
+    /// `offset`th latest entry, `1` being the latest.
+    pub fn nth_latest(&self, offset: usize) -> Option<&MessageHistoryEntry> {
+        self.iter().nth(offset.checked_sub(1)?)
+    }
+
+    /// Latest entry of the author.
+    pub fn latest_by(&self, author_id: UserId) -> Option<&MessageHistoryEntry> {
+        self.latest_by_author.get(&author_id)
+    }
+
+    /// `offset`th latest entry matching the filter, `1` being the
+    /// latest.
+    pub fn nth_latest_matching(
+        &self,
+        offset: usize,
+        filter: &MessageFilter,
+    ) -> Option<&MessageHistoryEntry> {
+        match filter {
+            MessageFilter {
+                author_id: Some(author_id),
+                contains: None,
+                guild_settings: None,
+            } if offset == 1 => self.latest_by(*author_id),
+            _ => self
+                .iter()
+                .filter(|entry| filter.matches(entry))
+                .nth(offset.checked_sub(1)?),
+        }
+    }
+
+    /// Entries from newest to oldest.
+    pub fn iter(&self) -> impl Iterator<Item = &MessageHistoryEntry> {
+        self.entries.iter().rev()
+    }
+
+    /// Check if the recent messages of the channel were fetched from
+    /// the API.
+    pub fn is_fetched(&self) -> bool {
+        self.fetched
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Recent message history of the channels.
+///
+/// Both the number of messages per channel and the number of channels
+/// are capped, the channels with the least recent activity are
+/// evicted first.
+#[derive(Debug, Clone)]
+pub struct MessageHistory {
+    /// [`ChannelId`] to its [`ChannelMessageHistory`].
+    channels: HashMap<ChannelId, ChannelMessageHistory>,
+    /// Maximum number of messages kept per channel.
+    max_messages_per_channel: usize,
+    /// Maximum number of channels kept.
+    max_channels: usize,
+    /// Keep the content of the messages.
+    keep_content: bool,
+}
+
+impl MessageHistory {
+    /// Default maximum number of messages kept per channel.
+    pub const DEFAULT_MAX_MESSAGES_PER_CHANNEL: usize = 50;
+    /// Default maximum number of channels kept.
+    pub const DEFAULT_MAX_CHANNELS: usize = 10_000;
+
+    /// Create a new [`MessageHistory`] with the given caps.
+    pub fn new(max_messages_per_channel: usize, max_channels: usize) -> Self {
+        Self {
+            channels: HashMap::new(),
+            max_messages_per_channel,
+            max_channels,
+            keep_content: false,
+        }
+    }
+
+    /// Keep the content of the messages, needed to filter them by
+    /// their content. Requires the message content intent.
+    pub fn with_content(mut self, keep_content: bool) -> Self {
+        self.keep_content = keep_content;
+        self
+    }
+
+    /// Check if the content of the messages is kept.
+    pub fn keeps_content(&self) -> bool {
+        self.keep_content
+    }
+
+    /// Entry of the message, without its content unless it is kept.
+    fn entry(&self, message: &Message) -> MessageHistoryEntry {
+        let mut entry = MessageHistoryEntry::from(message);
+        if !self.keep_content {
+            entry.content.clear();
+        }
+        entry
+    }
+
+    /// Maximum number of messages kept per channel.
+    pub fn max_messages_per_channel(&self) -> usize {
+        self.max_messages_per_channel
+    }
+
+    /// Insert the entry for the channel, dropping its content unless
+    /// it is kept.
+    pub fn insert(&mut self, channel_id: ChannelId, mut entry: MessageHistoryEntry) {
+        if !self.keep_content {
+            entry.content.clear();
+        }
+        self.channels
+            .entry(channel_id)
+            .or_default()
+            .insert(entry, self.max_messages_per_channel);
+
+        self.evict_channels();
+    }
+
+    /// Evict the least recently active channels until at most
+    /// `max_channels` are kept.
+    fn evict_channels(&mut self) {
+        while self.channels.len() > self.max_channels {
+            match self
+                .channels
+                .iter()
+                .min_by_key(|(_, history)| history.latest().map(|entry| entry.message_id))
+                .map(|(channel_id, _)| *channel_id)
+            {
+                Some(least_recent_channel_id) => {
+                    self.channels.remove(&least_recent_channel_id);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Insert the messages fetched from the API for the channel and
+    /// mark the channel as fetched.
+    ///
+    /// Only the messages passing [`is_tracked()`] are inserted.
+    pub fn insert_fetched(&mut self, channel_id: ChannelId, messages: &[Message], bot_id: UserId) {
+        let max_messages = self.max_messages_per_channel;
+        let entries = messages
+            .iter()
+            .filter(|message| is_tracked(message, bot_id))
+            .map(|message| self.entry(message))
+            .collect::<Vec<_>>();
+        let history = self.channels.entry(channel_id).or_default();
+        entries
+            .into_iter()
+            .for_each(|entry| history.insert(entry, max_messages));
+        history.fetched = true;
+
+        self.evict_channels();
+    }
+
+    /// Check if the history of the channel should be fetched from the
+    /// API to go back `count` messages.
+    pub fn needs_fetch(&self, channel_id: ChannelId, count: usize) -> bool {
+        match self.channels.get(&channel_id) {
+            Some(history) => !history.fetched && history.len() < count,
+            None => true,
+        }
+    }
+
+    /// Remove the entries of the deleted messages of the channel.
+    ///
+    /// The channel is pruned once no entries are left so that its
+    /// history is fetched again when needed.
+    pub fn remove(&mut self, channel_id: ChannelId, message_ids: &[MessageId]) {
+        if let Some(history) = self.channels.get_mut(&channel_id) {
+            let removed = message_ids
+                .iter()
+                .filter(|message_id| history.remove(**message_id))
+                .count();
+            if removed != 0 && history.is_empty() {
+                self.channels.remove(&channel_id);
+            }
+        }
+    }
+
+    /// Remove the history of the deleted channel.
+    pub fn remove_channel(&mut self, channel_id: ChannelId) {
+        self.channels.remove(&channel_id);
+    }
+
+    /// Update the content of the edited message of the channel, if the
+    /// content is kept.
+    pub fn update_content(&mut self, channel_id: ChannelId, message_id: MessageId, content: &str) {
+        if !self.keep_content {
+            return;
+        }
+        if let Some(history) = self.channels.get_mut(&channel_id) {
+            history.update_content(message_id, content);
+        }
+    }
+
+    /// [`ChannelMessageHistory`] of the channel.
+    pub fn channel(&self, channel_id: ChannelId) -> Option<&ChannelMessageHistory> {
+        self.channels.get(&channel_id)
+    }
+}
+
+impl Default for MessageHistory {
+    fn default() -> Self {
+        Self::new(
+            Self::DEFAULT_MAX_MESSAGES_PER_CHANNEL,
+            Self::DEFAULT_MAX_CHANNELS,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::{prelude::*, Timestamp};
+
+    use super::{MessageFilter, MessageHistory, MessageHistoryEntry};
+
+    fn entry(message_id: u64) -> MessageHistoryEntry {
+        entry_by(message_id, 1, "")
+    }
+
+    fn entry_by(message_id: u64, author_id: u64, content: &str) -> MessageHistoryEntry {
+        MessageHistoryEntry {
+            message_id: MessageId(message_id),
+            author_id: UserId(author_id),
+            timestamp: Timestamp::now(),
+            content: content.to_string(),
+            author_is_bot: false,
+            webhook_id: None,
+            kind: MessageType::Regular,
+        }
+    }
+
+    /// Entries are ordered and capped per channel.
+    #[test]
+    fn message_history_01() {
+        let mut message_history = MessageHistory::new(3, 10);
+        let channel_id = ChannelId(1);
+        [2, 1, 4, 3, 3].into_iter().for_each(|message_id| {
+            message_history.insert(channel_id, entry(message_id));
+        });
+
+        let history = message_history.channel(channel_id).unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.nth_latest(0), None);
+        assert_eq!(history.nth_latest(1).unwrap().message_id, MessageId(4));
+        assert_eq!(history.nth_latest(3).unwrap().message_id, MessageId(2));
+        assert_eq!(history.nth_latest(4), None);
+    }
+
+    /// Least recently active channel is evicted.
+    #[test]
+    fn message_history_02() {
+        let mut message_history = MessageHistory::new(3, 2);
+        message_history.insert(ChannelId(1), entry(5));
+        message_history.insert(ChannelId(2), entry(1));
+        message_history.insert(ChannelId(3), entry(3));
+
+        assert!(message_history.channel(ChannelId(1)).is_some());
+        assert!(message_history.channel(ChannelId(2)).is_none());
+        assert!(message_history.channel(ChannelId(3)).is_some());
+    }
+
+    /// Entries are filtered by author and content, the latest entry of
+    /// an author outlives the capped entries.
+    #[test]
+    fn message_history_03() {
+        let mut message_history = MessageHistory::new(2, 10).with_content(true);
+        let channel_id = ChannelId(1);
+        message_history.insert(channel_id, entry_by(1, 2, "Hello there"));
+        message_history.insert(channel_id, entry_by(2, 3, "hello again"));
+        message_history.insert(channel_id, entry_by(3, 3, "bye"));
+
+        let history = message_history.channel(channel_id).unwrap();
+        let by_author = |author_id| MessageFilter::new(Some(UserId(author_id)), None);
+        assert_eq!(
+            history
+                .nth_latest_matching(1, &by_author(2))
+                .unwrap()
+                .message_id,
+            MessageId(1)
+        );
+        assert_eq!(
+            history
+                .nth_latest_matching(2, &by_author(3))
+                .unwrap()
+                .message_id,
+            MessageId(2)
+        );
+        assert_eq!(history.nth_latest_matching(2, &by_author(2)), None);
+
+        let containing = MessageFilter::new(None, Some(" HELLO "));
+        assert_eq!(
+            history
+                .nth_latest_matching(1, &containing)
+                .unwrap()
+                .message_id,
+            MessageId(2)
+        );
+        assert_eq!(history.nth_latest_matching(2, &containing), None);
+        assert!(!MessageFilter::new(None, Some("  ")).has_criteria());
+    }
+
+    /// Deleted entries fall back to the previous ones and empty
+    /// channels are pruned.
+    #[test]
+    fn message_history_04() {
+        let mut message_history = MessageHistory::new(5, 10).with_content(true);
+        let channel_id = ChannelId(1);
+        message_history.insert(channel_id, entry_by(1, 2, ""));
+        message_history.insert(channel_id, entry_by(2, 3, ""));
+        message_history.insert(channel_id, entry_by(3, 2, "typo"));
+        message_history.update_content(channel_id, MessageId(3), "fixed");
+
+        let history = message_history.channel(channel_id).unwrap();
+        assert_eq!(history.latest_by(UserId(2)).unwrap().content, "fixed");
+
+        message_history.remove(channel_id, &[MessageId(3)]);
+        let history = message_history.channel(channel_id).unwrap();
+        assert_eq!(history.latest().unwrap().message_id, MessageId(2));
+        assert_eq!(
+            history.latest_by(UserId(2)).unwrap().message_id,
+            MessageId(1)
+        );
+
+        message_history.remove(channel_id, &[MessageId(1), MessageId(2)]);
+        assert!(message_history.channel(channel_id).is_none());
+    }
+
+    /// Content is dropped unless it is kept.
+    #[test]
+    fn message_history_05() {
+        let mut message_history = MessageHistory::new(5, 10);
+        let channel_id = ChannelId(1);
+        message_history.insert(channel_id, entry_by(1, 2, "secret"));
+        message_history.update_content(channel_id, MessageId(1), "edited");
+
+        let history = message_history.channel(channel_id).unwrap();
+        assert_eq!(history.latest().unwrap().content, "");
+        assert!(!message_history.keeps_content());
+    }
+}
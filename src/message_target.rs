@@ -0,0 +1,367 @@
+//! Message targeted by a command.
+
+use std::str::FromStr;
+
+use serenity::{cache::Cache, model::prelude::*};
+
+use crate::{
+    message_history::{MessageFilter, MessageHistory},
+    permissions::permission_channel,
+};
+
+/// Message targeted by a command.
+///
+/// Can be parsed from a bare message ID, the `channelId-messageId`
+/// form given by "Copy ID", a full message link, a relative target
+/// such as `^`, `^^` or `^3` for the messages above or `starter` for
+/// the starter message of the thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageTarget {
+    /// Message given by its ID.
+    Id {
+        /// [`GuildId`] of the message, only known for message links.
+        guild_id: Option<GuildId>,
+        /// [`ChannelId`] of the message, [`None`] if the message is
+        /// in the channel of the interaction.
+        channel_id: Option<ChannelId>,
+        /// [`MessageId`].
+        message_id: MessageId,
+    },
+    /// `offset`th latest message of the channel of the interaction,
+    /// `1` being the latest.
+    Relative(usize),
+    /// Starter message of the thread of the interaction, the post of
+    /// a forum thread.
+    ThreadStarter,
+}
+
+impl MessageTarget {
+    /// Hosts accepted for message links.
+    const LINK_HOSTS: &'static [&'static str] = &[
+        "discord.com",
+        "ptb.discord.com",
+        "canary.discord.com",
+        "discordapp.com",
+        "ptb.discordapp.com",
+        "canary.discordapp.com",
+    ];
+
+    /// Latest message of the channel of the interaction.
+    pub const LATEST: Self = Self::Relative(1);
+
+    /// Keyword for [`Self::ThreadStarter`].
+    const THREAD_STARTER: &'static str = "starter";
+
+    /// Resolve the target to the [`ChannelId`] and [`MessageId`] of
+    /// the message. `channel_id` is the channel of the interaction.
+    ///
+    /// Relative targets are looked up in the [`MessageHistory`]
+    /// counting only the messages matching the filter, [`None`] if the
+    /// history does not go back far enough. Thread starters are
+    /// [`None`] outside of cached threads and in private threads, which
+    /// are never started from a message. Other threads may not have
+    /// been started from a message either, so the starter outside of
+    /// forum posts may not exist.
+    pub fn resolve(
+        &self,
+        cache: &Cache,
+        channel_id: ChannelId,
+        message_history: &MessageHistory,
+        filter: &MessageFilter,
+    ) -> Option<(ChannelId, MessageId)> {
+        match self {
+            Self::Id {
+                channel_id: target_channel_id,
+                message_id,
+                ..
+            } => Some((target_channel_id.unwrap_or(channel_id), *message_id)),
+            Self::Relative(offset) => message_history
+                .channel(channel_id)?
+                .nth_latest_matching(*offset, filter)
+                .map(|entry| (channel_id, entry.message_id)),
+            Self::ThreadStarter => {
+                // Threads share the ID of their starter message, which
+                // is within the thread for forum posts and within the
+                // parent channel otherwise.
+                let thread = cache.guild_channel(channel_id)?;
+                if thread.kind == ChannelType::PrivateThread {
+                    return None;
+                }
+                let parent_id = thread.thread_metadata.and(thread.parent_id)?;
+                let starter_channel_id = match cache.guild_channel(parent_id) {
+                    Some(parent) if parent.kind == ChannelType::Forum => channel_id,
+                    _ => parent_id,
+                };
+                Some((starter_channel_id, MessageId(channel_id.0)))
+            }
+        }
+    }
+
+    /// Parse a relative target of the form `^`, `^^` or `^3`.
+    fn parse_relative(s: &str) -> Option<Self> {
+        let carets = s.strip_prefix('^')?;
+        let offset = if carets.is_empty() || carets.chars().all(|c| c == '^') {
+            carets.len() + 1
+        } else {
+            carets.parse().ok().filter(|offset| *offset > 0)?
+        };
+        Some(Self::Relative(offset))
+    }
+
+    /// Parse a message link of the form
+    /// `https://discord.com/channels/guild/channel/message`.
+    fn parse_link(link: &str) -> Option<Self> {
+        let link = link
+            .strip_prefix("https://")
+            .or_else(|| link.strip_prefix("http://"))?;
+        let (host, path) = link.split_once('/')?;
+        if !Self::LINK_HOSTS.contains(&host) {
+            return None;
+        }
+
+        let mut components = path.strip_prefix("channels/")?.split('/');
+        let guild_id = match components.next()? {
+            "@me" => None,
+            guild_id => Some(GuildId(guild_id.parse().ok()?)),
+        };
+        let channel_id = ChannelId(components.next()?.parse().ok()?);
+        let message_id = MessageId(components.next()?.parse().ok()?);
+        if components.next().is_some() {
+            return None;
+        }
+
+        Some(Self::Id {
+            guild_id,
+            channel_id: Some(channel_id),
+            message_id,
+        })
+    }
+
+    /// Check that the target can be reacted to on behalf of the
+    /// given [`Member`] from within the guild `guild_id`.
+    ///
+    /// Targets in the channel of the interaction are always
+    /// accessible. Otherwise the target must be in the same guild and
+    /// the member must be able to view the channel of the target.
+    pub fn check_access(
+        &self,
+        cache: &Cache,
+        guild_id: Option<GuildId>,
+        member: Option<&Member>,
+    ) -> Result<(), MessageTargetError> {
+        let (target_guild_id, channel_id) = match self {
+            Self::Id {
+                guild_id,
+                channel_id: Some(channel_id),
+                ..
+            } => (*guild_id, *channel_id),
+            _ => return Ok(()),
+        };
+
+        let (guild_id, member) = match (guild_id, member) {
+            (Some(guild_id), Some(member)) => (guild_id, member),
+            _ => return Err(MessageTargetError::NotInGuild),
+        };
+
+        if let Some(target_guild_id) = target_guild_id {
+            if target_guild_id != guild_id {
+                return Err(MessageTargetError::DifferentGuild(target_guild_id));
+            }
+        }
+
+        let guild = cache
+            .guild(guild_id)
+            .ok_or(MessageTargetError::UnknownGuild(guild_id))?;
+
+        let channel = permission_channel(&guild, channel_id)
+            .ok_or(MessageTargetError::UnknownChannel(channel_id))?;
+
+        let permissions = guild
+            .user_permissions_in(channel, member)
+            .map_err(|err| MessageTargetError::Permissions(Box::new(err)))?;
+        if permissions.view_channel() {
+            Ok(())
+        } else {
+            Err(MessageTargetError::ChannelNotVisible(channel_id))
+        }
+    }
+}
+
+impl FromStr for MessageTarget {
+    type Err = MessageTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || MessageTargetError::Invalid(s.to_string());
+
+        if let Ok(message_id) = s.parse::<u64>() {
+            return Ok(Self::Id {
+                guild_id: None,
+                channel_id: None,
+                message_id: MessageId(message_id),
+            });
+        }
+
+        if let Some((channel_id, message_id)) = s.split_once('-') {
+            return match (channel_id.parse::<u64>(), message_id.parse::<u64>()) {
+                (Ok(channel_id), Ok(message_id)) => Ok(Self::Id {
+                    guild_id: None,
+                    channel_id: Some(ChannelId(channel_id)),
+                    message_id: MessageId(message_id),
+                }),
+                _ => Err(invalid()),
+            };
+        }
+
+        if s.eq_ignore_ascii_case(Self::THREAD_STARTER) {
+            return Ok(Self::ThreadStarter);
+        }
+
+        Self::parse_relative(s)
+            .or_else(|| Self::parse_link(s))
+            .ok_or_else(invalid)
+    }
+}
+
+/// [`MessageTarget`] related errors.
+#[derive(Debug)]
+pub enum MessageTargetError {
+    /// Value is not a valid target.
+    Invalid(String),
+    /// Other channels were targeted outside of a guild.
+    NotInGuild,
+    /// Message is in another guild than the interaction.
+    DifferentGuild(GuildId),
+    /// Guild is not in the cache.
+    UnknownGuild(GuildId),
+    /// Channel is not in the cache.
+    UnknownChannel(ChannelId),
+    /// Member cannot view the channel.
+    ChannelNotVisible(ChannelId),
+    /// Permissions of the member could not be computed.
+    Permissions(Box<serenity::Error>),
+}
+
+impl std::fmt::Display for MessageTargetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(value) => write!(
+                f,
+                "expected message id, `channelId-messageId`, message link, `^` or `starter`, \
+                 got `{}`",
+                value
+            ),
+            Self::NotInGuild => write!(f, "other channels can only be targeted in a server"),
+            Self::DifferentGuild(guild_id) => {
+                write!(f, "message is in a different server `{}`", guild_id)
+            }
+            Self::UnknownGuild(guild_id) => write!(f, "server `{}` is not known", guild_id),
+            Self::UnknownChannel(channel_id) => write!(f, "channel {} is not known", channel_id),
+            Self::ChannelNotVisible(channel_id) => {
+                write!(f, "you cannot view the channel {}", channel_id.mention())
+            }
+            Self::Permissions(err) => write!(f, "could not compute permissions: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MessageTargetError {}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::prelude::*;
+
+    use super::MessageTarget;
+
+    /// Bare message ID.
+    #[test]
+    fn message_target_01() {
+        assert_eq!(
+            "1234".parse::<MessageTarget>().unwrap(),
+            MessageTarget::Id {
+                guild_id: None,
+                channel_id: None,
+                message_id: MessageId(1234),
+            }
+        );
+    }
+
+    /// `channelId-messageId` form.
+    #[test]
+    fn message_target_02() {
+        assert_eq!(
+            "12-34".parse::<MessageTarget>().unwrap(),
+            MessageTarget::Id {
+                guild_id: None,
+                channel_id: Some(ChannelId(12)),
+                message_id: MessageId(34),
+            }
+        );
+        assert!("12-".parse::<MessageTarget>().is_err());
+        assert!("a-34".parse::<MessageTarget>().is_err());
+    }
+
+    /// Message links.
+    #[test]
+    fn message_target_03() {
+        let expected = MessageTarget::Id {
+            guild_id: Some(GuildId(1)),
+            channel_id: Some(ChannelId(2)),
+            message_id: MessageId(3),
+        };
+        assert_eq!(
+            "https://discord.com/channels/1/2/3"
+                .parse::<MessageTarget>()
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            "https://canary.discordapp.com/channels/1/2/3"
+                .parse::<MessageTarget>()
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            "https://discord.com/channels/@me/2/3"
+                .parse::<MessageTarget>()
+                .unwrap(),
+            MessageTarget::Id {
+                guild_id: None,
+                channel_id: Some(ChannelId(2)),
+                message_id: MessageId(3),
+            }
+        );
+        assert!("https://example.com/channels/1/2/3"
+            .parse::<MessageTarget>()
+            .is_err());
+        assert!("https://discord.com/channels/1/2/3/4"
+            .parse::<MessageTarget>()
+            .is_err());
+        assert!("https://discord.com/channels/1/2"
+            .parse::<MessageTarget>()
+            .is_err());
+    }
+
+    /// Relative targets.
+    #[test]
+    fn message_target_04() {
+        assert_eq!(
+            "^".parse::<MessageTarget>().unwrap(),
+            MessageTarget::Relative(1)
+        );
+        assert_eq!(
+            "^^^".parse::<MessageTarget>().unwrap(),
+            MessageTarget::Relative(3)
+        );
+        assert_eq!(
+            "^5".parse::<MessageTarget>().unwrap(),
+            MessageTarget::Relative(5)
+        );
+        assert!("^0".parse::<MessageTarget>().is_err());
+        assert!("^^5".parse::<MessageTarget>().is_err());
+        assert_eq!(
+            "Starter".parse::<MessageTarget>().unwrap(),
+            MessageTarget::ThreadStarter
+        );
+    }
+}
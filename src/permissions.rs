@@ -0,0 +1,169 @@
+//! Permissions needed to react to a message, checked from the cache
+//! before reacting.
+
+use serenity::{cache::Cache, model::prelude::*};
+
+/// Permissions needed to react to a message with any emoji.
+pub const REACT_PERMISSIONS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::READ_MESSAGE_HISTORY)
+    .union(Permissions::ADD_REACTIONS);
+
+/// Permissions needed to react with an emoji of another guild.
+pub const EXTERNAL_EMOJI_PERMISSIONS: Permissions = Permissions::USE_EXTERNAL_EMOJIS;
+
+/// Effective permissions of the bot and of the user in a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReactionPermissions {
+    /// Permissions of the bot.
+    pub bot: Permissions,
+    /// Permissions of the user the bot reacts for.
+    pub user: Permissions,
+    /// Channel the permissions apply to.
+    pub channel_id: ChannelId,
+}
+
+impl ReactionPermissions {
+    /// Compute the permissions of the bot and of the `member` in the
+    /// channel, threads using the permissions of their parent.
+    ///
+    /// [`None`] if the cache lacks the guild, the channel or the bot's
+    /// member.
+    pub fn compute(cache: &Cache, channel_id: ChannelId, member: &Member) -> Option<Self> {
+        let guild = cache.guild(member.guild_id)?;
+        let channel = permission_channel(&guild, channel_id)?;
+        let bot_member = guild.members.get(&cache.current_user_id())?;
+
+        let permissions_of = |member: &Member| match guild.user_permissions_in(channel, member) {
+            Ok(permissions) => Some(permissions),
+            Err(err) => {
+                tracing::error!(
+                    "couldn't compute permissions of `{}` in `{}` due to `{}`",
+                    member.user.tag(),
+                    channel_id,
+                    err
+                );
+                None
+            }
+        };
+
+        Some(Self {
+            bot: permissions_of(bot_member)?,
+            user: permissions_of(member)?,
+            channel_id,
+        })
+    }
+
+    /// Check that both the bot and the user have the `required`
+    /// permissions.
+    pub fn check(&self, required: Permissions) -> Result<(), MissingPermissions> {
+        [(Subject::Bot, self.bot), (Subject::User, self.user)]
+            .into_iter()
+            .map(|(subject, permissions)| MissingPermissions {
+                subject,
+                permissions: required - permissions,
+                channel_id: self.channel_id,
+            })
+            .find(|missing| !missing.permissions.is_empty())
+            .map_or(Ok(()), Err)
+    }
+}
+
+/// Channel whose permissions apply to `channel_id`, its parent
+/// channel for threads.
+pub fn permission_channel(guild: &Guild, channel_id: ChannelId) -> Option<&GuildChannel> {
+    let channel_id = match guild.channels.get(&channel_id) {
+        Some(_) => channel_id,
+        None => guild
+            .threads
+            .iter()
+            .find(|thread| thread.id == channel_id)
+            .and_then(|thread| thread.parent_id)?,
+    };
+    match guild.channels.get(&channel_id) {
+        Some(Channel::Guild(channel)) => Some(channel),
+        _ => None,
+    }
+}
+
+/// Check if the member can view the channel, channels that are not in
+/// the guild cannot be.
+pub fn can_view_channel(guild: &Guild, channel_id: ChannelId, member: &Member) -> bool {
+    permission_channel(guild, channel_id)
+        .and_then(|channel| guild.user_permissions_in(channel, member).ok())
+        .is_some_and(|permissions| permissions.view_channel())
+}
+
+/// Who lacks the permissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    /// The bot.
+    Bot,
+    /// The user the bot reacts for.
+    User,
+}
+
+/// Permissions the [`Subject`] lacks in the channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingPermissions {
+    /// Who lacks the permissions.
+    pub subject: Subject,
+    /// Missing permissions.
+    pub permissions: Permissions,
+    /// Channel the permissions are missing in.
+    pub channel_id: ChannelId,
+}
+
+impl std::fmt::Display for MissingPermissions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = self.permissions.get_permission_names();
+        write!(
+            f,
+            "{} missing the {} permission{} in {}",
+            match self.subject {
+                Subject::Bot => "the bot is",
+                Subject::User => "you are",
+            },
+            names.join(", "),
+            if names.len() == 1 { "" } else { "s" },
+            self.channel_id.mention(),
+        )
+    }
+}
+
+impl std::error::Error for MissingPermissions {}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::prelude::*;
+
+    use super::{ReactionPermissions, Subject, EXTERNAL_EMOJI_PERMISSIONS, REACT_PERMISSIONS};
+
+    /// The bot's missing permissions are reported before the user's.
+    #[test]
+    fn permissions_01() {
+        let mut permissions = ReactionPermissions {
+            bot: REACT_PERMISSIONS - Permissions::ADD_REACTIONS,
+            user: Permissions::VIEW_CHANNEL,
+            channel_id: ChannelId(1),
+        };
+        let missing = permissions.check(REACT_PERMISSIONS).unwrap_err();
+        assert_eq!(missing.subject, Subject::Bot);
+        assert_eq!(missing.permissions, Permissions::ADD_REACTIONS);
+        assert_eq!(
+            missing.to_string(),
+            "the bot is missing the Add Reactions permission in <#1>"
+        );
+
+        permissions.bot = REACT_PERMISSIONS;
+        let missing = permissions.check(REACT_PERMISSIONS).unwrap_err();
+        assert_eq!(missing.subject, Subject::User);
+        assert_eq!(
+            missing.permissions,
+            Permissions::READ_MESSAGE_HISTORY | Permissions::ADD_REACTIONS
+        );
+
+        permissions.user = REACT_PERMISSIONS;
+        assert!(permissions.check(REACT_PERMISSIONS).is_ok());
+        assert!(permissions.check(EXTERNAL_EMOJI_PERMISSIONS).is_err());
+    }
+}
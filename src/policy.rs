@@ -0,0 +1,274 @@
+//! Per guild policy of who can use the commands and where.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::{cache::Cache, model::prelude::*};
+
+/// Access given to a role or channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Explicitly allowed, once anything is allowed everything else
+    /// is denied.
+    Allow,
+    /// Explicitly denied.
+    Deny,
+}
+
+/// Allowed and denied IDs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessList<T: Eq + Hash> {
+    /// Allowed IDs, all but the denied ones if empty.
+    pub allowed: HashSet<T>,
+    /// Denied IDs.
+    pub denied: HashSet<T>,
+}
+
+impl<T: Copy + Eq + Hash> AccessList<T> {
+    /// Set the [`Access`] of the ID, [`None`] clearing it.
+    pub fn set(&mut self, id: T, access: Option<Access>) {
+        self.allowed.remove(&id);
+        self.denied.remove(&id);
+        match access {
+            Some(Access::Allow) => self.allowed.insert(id),
+            Some(Access::Deny) => self.denied.insert(id),
+            None => false,
+        };
+    }
+
+    /// Check if the list has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.allowed.is_empty() && self.denied.is_empty()
+    }
+
+    /// Check the IDs against the list. Any denied ID denies access,
+    /// otherwise one of them must be allowed if the list allows any.
+    pub fn check(&self, ids: &[T]) -> Result<(), AccessDenied<T>> {
+        if let Some(id) = ids.iter().find(|id| self.denied.contains(id)) {
+            return Err(AccessDenied::Denied(*id));
+        }
+        if self.allowed.is_empty() || ids.iter().any(|id| self.allowed.contains(id)) {
+            Ok(())
+        } else {
+            Err(AccessDenied::NotAllowed)
+        }
+    }
+}
+
+impl<T: Eq + Hash> Default for AccessList<T> {
+    fn default() -> Self {
+        Self {
+            allowed: HashSet::new(),
+            denied: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Eq + Hash + Mentionable + Ord> std::fmt::Display for AccessList<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mentions = |ids: &HashSet<T>| {
+            if ids.is_empty() {
+                return "none".to_string();
+            }
+            let mut ids = ids.iter().collect::<Vec<_>>();
+            ids.sort();
+            ids.into_iter()
+                .map(|id| id.mention().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        write!(
+            f,
+            "allowed: {}, denied: {}",
+            mentions(&self.allowed),
+            mentions(&self.denied)
+        )
+    }
+}
+
+/// Reason an [`AccessList`] denies access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDenied<T> {
+    /// The ID is denied.
+    Denied(T),
+    /// None of the IDs are allowed.
+    NotAllowed,
+}
+
+/// Policy of a guild.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildPolicy {
+    /// Channels the commands can be used in.
+    pub channels: AccessList<ChannelId>,
+    /// Command name to the roles that can use it.
+    pub command_roles: HashMap<String, AccessList<RoleId>>,
+}
+
+impl GuildPolicy {
+    /// Set the [`Access`] of the role to the command, [`None`]
+    /// clearing it.
+    pub fn set_command_role(
+        &mut self,
+        command_name: &str,
+        role_id: RoleId,
+        access: Option<Access>,
+    ) {
+        let roles = self
+            .command_roles
+            .entry(command_name.to_string())
+            .or_default();
+        roles.set(role_id, access);
+        if roles.is_empty() {
+            self.command_roles.remove(command_name);
+        }
+    }
+
+    /// Check if the command can be used in the channel, given with its
+    /// parent for threads, by a member with the roles.
+    pub fn check(
+        &self,
+        command_name: &str,
+        channel_ids: &[ChannelId],
+        role_ids: &[RoleId],
+    ) -> Result<(), PolicyError> {
+        self.channels.check(channel_ids).map_err(|err| match err {
+            AccessDenied::Denied(channel_id) => PolicyError::ChannelDenied(channel_id),
+            AccessDenied::NotAllowed => PolicyError::ChannelNotAllowed,
+        })?;
+
+        match self.command_roles.get(command_name) {
+            Some(roles) => roles.check(role_ids).map_err(|err| match err {
+                AccessDenied::Denied(role_id) => PolicyError::RoleDenied(role_id),
+                AccessDenied::NotAllowed => PolicyError::RoleNotAllowed,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for GuildPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "channels - {}", self.channels)?;
+        let mut command_roles = self.command_roles.iter().collect::<Vec<_>>();
+        command_roles.sort_by_key(|(command_name, _)| command_name.as_str());
+        for (command_name, roles) in command_roles {
+            write!(f, "\n`{}` roles - {}", command_name, roles)?;
+        }
+        Ok(())
+    }
+}
+
+/// Channel and, for threads, its parent channel, whose policies both
+/// apply to the channel.
+pub fn policy_channel_ids(
+    cache: &Cache,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Vec<ChannelId> {
+    let parent_id = cache.guild(guild_id).and_then(|guild| {
+        guild
+            .threads
+            .iter()
+            .find(|thread| thread.id == channel_id)
+            .and_then(|thread| thread.parent_id)
+    });
+    std::iter::once(channel_id).chain(parent_id).collect()
+}
+
+/// Roles of the member, including the `@everyone` role of the guild.
+pub fn policy_role_ids(member: &Member) -> Vec<RoleId> {
+    std::iter::once(RoleId(member.guild_id.0))
+        .chain(member.roles.iter().copied())
+        .collect()
+}
+
+/// Errors of [`GuildPolicy::check()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyError {
+    /// Channel, or its parent, is denied.
+    ChannelDenied(ChannelId),
+    /// Some channels are allowed but not this one.
+    ChannelNotAllowed,
+    /// Member has a role denied for the command.
+    RoleDenied(RoleId),
+    /// Some roles are allowed for the command but the member has none of them.
+    RoleNotAllowed,
+}
+
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyError::ChannelDenied(channel_id) => {
+                write!(f, "commands cannot be used in {}", channel_id.mention())
+            }
+            PolicyError::ChannelNotAllowed => {
+                write!(f, "commands cannot be used in this channel")
+            }
+            PolicyError::RoleDenied(role_id) => {
+                write!(
+                    f,
+                    "command cannot be used with the role {}",
+                    role_id.mention()
+                )
+            }
+            PolicyError::RoleNotAllowed => {
+                write!(f, "command requires one of the allowed roles")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::prelude::*;
+
+    use super::{Access, GuildPolicy, PolicyError};
+
+    /// Denied channels and roles win, allow lists restrict the rest.
+    #[test]
+    fn guild_policy_01() {
+        let mut policy = GuildPolicy::default();
+        assert!(policy
+            .check("add_reaction", &[ChannelId(1)], &[RoleId(1)])
+            .is_ok());
+
+        policy.channels.set(ChannelId(2), Some(Access::Deny));
+        assert_eq!(
+            policy.check("add_reaction", &[ChannelId(3), ChannelId(2)], &[]),
+            Err(PolicyError::ChannelDenied(ChannelId(2)))
+        );
+        policy.channels.set(ChannelId(1), Some(Access::Allow));
+        assert!(policy.check("add_reaction", &[ChannelId(1)], &[]).is_ok());
+        assert_eq!(
+            policy.check("add_reaction", &[ChannelId(3)], &[]),
+            Err(PolicyError::ChannelNotAllowed)
+        );
+
+        policy.set_command_role("add_reaction", RoleId(5), Some(Access::Allow));
+        policy.set_command_role("add_reaction", RoleId(6), Some(Access::Deny));
+        assert!(policy
+            .check("add_reaction", &[ChannelId(1)], &[RoleId(5)])
+            .is_ok());
+        assert!(policy
+            .check("text_to_reactions", &[ChannelId(1)], &[])
+            .is_ok());
+        assert_eq!(
+            policy.check("add_reaction", &[ChannelId(1)], &[RoleId(5), RoleId(6)]),
+            Err(PolicyError::RoleDenied(RoleId(6)))
+        );
+        assert_eq!(
+            policy.check("add_reaction", &[ChannelId(1)], &[RoleId(7)]),
+            Err(PolicyError::RoleNotAllowed)
+        );
+
+        policy.set_command_role("add_reaction", RoleId(5), None);
+        policy.set_command_role("add_reaction", RoleId(6), None);
+        assert!(policy.command_roles.is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+//! Cooldowns of the commands and caps on the reactions, to keep one
+//! user from using up the bot's Discord rate limit.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use serenity::model::prelude::*;
+
+/// Token bucket holding up to `capacity` tokens, refilled at a
+/// constant rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenBucket {
+    /// Tokens available.
+    tokens: f64,
+    /// Time the tokens were last refilled.
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket.
+    fn new(limit: Limit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.capacity),
+            updated: now,
+        }
+    }
+
+    /// Refill the tokens up to the time `now`.
+    fn refill(&mut self, limit: Limit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.rate()).min(f64::from(limit.capacity));
+        self.updated = now;
+    }
+
+    /// Time until `amount` tokens are available, zero if they already
+    /// are.
+    fn wait_for(&self, limit: Limit, amount: f64) -> Duration {
+        if self.tokens >= amount {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((amount - self.tokens) / limit.rate())
+        }
+    }
+
+    /// Check if the bucket is full, so dropping it changes nothing.
+    fn is_full(&self, limit: Limit) -> bool {
+        self.tokens >= f64::from(limit.capacity)
+    }
+}
+
+/// Limit of a [`TokenBucket`], `capacity` uses per `period`.
+///
+/// Parsed from `capacity/seconds`, such as `5/30` for 5 uses per 30
+/// seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limit {
+    /// Uses allowed at once.
+    capacity: u32,
+    /// Time to refill all the uses.
+    period: Duration,
+}
+
+impl Limit {
+    /// Create a new [`Limit`], both the capacity and the period must
+    /// be non zero.
+    pub fn new(capacity: u32, period: Duration) -> Result<Self, LimitError> {
+        if capacity == 0 {
+            return Err(LimitError::ZeroCapacity);
+        }
+        if period.is_zero() {
+            return Err(LimitError::ZeroPeriod);
+        }
+        Ok(Self { capacity, period })
+    }
+
+    /// Uses allowed at once.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Time to refill all the uses.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Tokens refilled per second.
+    fn rate(&self) -> f64 {
+        f64::from(self.capacity) / self.period.as_secs_f64()
+    }
+}
+
+impl std::str::FromStr for Limit {
+    type Err = LimitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || LimitError::Invalid(s.to_string());
+        let (capacity, seconds) = s.split_once('/').ok_or_else(invalid)?;
+        let capacity = capacity.trim().parse().map_err(|_| invalid())?;
+        let seconds = seconds.trim().parse().map_err(|_| invalid())?;
+        Self::new(capacity, Duration::from_secs(seconds))
+    }
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.capacity, self.period.as_secs())
+    }
+}
+
+/// Configuration of the [`RateLimiter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Commands per user.
+    pub user_commands: Limit,
+    /// Commands per channel.
+    pub channel_commands: Limit,
+    /// Commands per guild.
+    pub guild_commands: Limit,
+    /// Reactions a single request can add.
+    pub max_reactions_per_request: usize,
+    /// Reactions per user.
+    pub user_reactions: Limit,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            user_commands: Limit {
+                capacity: 5,
+                period: Duration::from_secs(30),
+            },
+            channel_commands: Limit {
+                capacity: 10,
+                period: Duration::from_secs(30),
+            },
+            guild_commands: Limit {
+                capacity: 30,
+                period: Duration::from_secs(30),
+            },
+            // Discord allows at most 20 different reactions on a
+            // message.
+            max_reactions_per_request: 20,
+            user_reactions: Limit {
+                capacity: 40,
+                period: Duration::from_secs(60),
+            },
+        }
+    }
+}
+
+/// Token buckets of the IDs of a kind, sharing a [`Limit`].
+#[derive(Debug)]
+struct Buckets<T> {
+    /// Limit of the buckets.
+    limit: Limit,
+    /// Buckets by ID, missing ones are full.
+    buckets: HashMap<T, TokenBucket>,
+}
+
+impl<T: Copy + Eq + Hash> Buckets<T> {
+    /// Create new [`Buckets`].
+    fn new(limit: Limit) -> Self {
+        Self {
+            limit,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Time until `amount` tokens are available for the ID.
+    fn wait_for(&mut self, id: T, amount: f64, now: Instant) -> Duration {
+        let limit = self.limit;
+        let bucket = self
+            .buckets
+            .entry(id)
+            .or_insert_with(|| TokenBucket::new(limit, now));
+        bucket.refill(limit, now);
+        bucket.wait_for(limit, amount)
+    }
+
+    /// Take `amount` tokens of the ID, they must be available.
+    fn take(&mut self, id: T, amount: f64) {
+        if let Some(bucket) = self.buckets.get_mut(&id) {
+            bucket.tokens -= amount;
+        }
+    }
+
+    /// Drop the full buckets.
+    fn prune(&mut self, now: Instant) {
+        let limit = self.limit;
+        self.buckets.retain(|_, bucket| {
+            bucket.refill(limit, now);
+            !bucket.is_full(limit)
+        });
+    }
+}
+
+/// Rate limiter of the commands and reactions.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Configuration.
+    config: RateLimitConfig,
+    /// Commands per user.
+    user_commands: Buckets<UserId>,
+    /// Commands per channel.
+    channel_commands: Buckets<ChannelId>,
+    /// Commands per guild.
+    guild_commands: Buckets<GuildId>,
+    /// Reactions per user.
+    user_reactions: Buckets<UserId>,
+    /// Time the full buckets were last dropped.
+    pruned: Instant,
+}
+
+impl RateLimiter {
+    /// Interval to drop the full buckets at.
+    const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Create a new [`RateLimiter`].
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            user_commands: Buckets::new(config.user_commands),
+            channel_commands: Buckets::new(config.channel_commands),
+            guild_commands: Buckets::new(config.guild_commands),
+            user_reactions: Buckets::new(config.user_reactions),
+            pruned: Instant::now(),
+        }
+    }
+
+    /// Count a command of the user in the channel, unless any of the
+    /// cooldowns is active. Nothing is counted if the command is
+    /// refused.
+    pub fn check_command(
+        &mut self,
+        user_id: UserId,
+        channel_id: ChannelId,
+        guild_id: Option<GuildId>,
+        now: Instant,
+    ) -> Result<(), RateLimited> {
+        self.prune(now);
+
+        let user_wait = self.user_commands.wait_for(user_id, 1.0, now);
+        if !user_wait.is_zero() {
+            return Err(RateLimited::User(user_wait));
+        }
+        let channel_wait = self.channel_commands.wait_for(channel_id, 1.0, now);
+        if !channel_wait.is_zero() {
+            return Err(RateLimited::Channel(channel_wait));
+        }
+        if let Some(guild_id) = guild_id {
+            let guild_wait = self.guild_commands.wait_for(guild_id, 1.0, now);
+            if !guild_wait.is_zero() {
+                return Err(RateLimited::Guild(guild_wait));
+            }
+            self.guild_commands.take(guild_id, 1.0);
+        }
+        self.user_commands.take(user_id, 1.0);
+        self.channel_commands.take(channel_id, 1.0);
+
+        Ok(())
+    }
+
+    /// Count `count` reactions of a single request of the user, unless
+    /// it exceeds the caps. Nothing is counted if the request is
+    /// refused.
+    pub fn check_reactions(
+        &mut self,
+        user_id: UserId,
+        count: usize,
+        now: Instant,
+    ) -> Result<(), RateLimited> {
+        if count > self.config.max_reactions_per_request {
+            return Err(RateLimited::TooManyReactions(
+                self.config.max_reactions_per_request,
+            ));
+        }
+        if count > self.config.user_reactions.capacity as usize {
+            return Err(RateLimited::TooManyReactions(
+                self.config.user_reactions.capacity as usize,
+            ));
+        }
+
+        let amount = count as f64;
+        let wait = self.user_reactions.wait_for(user_id, amount, now);
+        if !wait.is_zero() {
+            return Err(RateLimited::Reactions(wait));
+        }
+        self.user_reactions.take(user_id, amount);
+
+        Ok(())
+    }
+
+    /// Drop the full buckets every [`Self::PRUNE_INTERVAL`].
+    fn prune(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.pruned) < Self::PRUNE_INTERVAL {
+            return;
+        }
+        self.user_commands.prune(now);
+        self.channel_commands.prune(now);
+        self.guild_commands.prune(now);
+        self.user_reactions.prune(now);
+        self.pruned = now;
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+/// Limit that was hit, with the time to wait before trying again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimited {
+    /// User used commands too quickly.
+    User(Duration),
+    /// Commands were used too quickly in the channel.
+    Channel(Duration),
+    /// Commands were used too quickly in the guild.
+    Guild(Duration),
+    /// More reactions than the maximum were requested at once.
+    TooManyReactions(usize),
+    /// User added too many reactions recently.
+    Reactions(Duration),
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seconds = |wait: &Duration| wait.as_secs_f64().ceil().max(1.0) as u64;
+        match self {
+            RateLimited::User(wait) => write!(
+                f,
+                "you are using commands too quickly, try again in {} seconds",
+                seconds(wait)
+            ),
+            RateLimited::Channel(wait) => write!(
+                f,
+                "commands are used too quickly in this channel, try again in {} seconds",
+                seconds(wait)
+            ),
+            RateLimited::Guild(wait) => write!(
+                f,
+                "commands are used too quickly in this server, try again in {} seconds",
+                seconds(wait)
+            ),
+            RateLimited::TooManyReactions(max) => {
+                write!(f, "at most {} reactions can be added at once", max)
+            }
+            RateLimited::Reactions(wait) => write!(
+                f,
+                "you added too many reactions recently, try again in {} seconds",
+                seconds(wait)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Errors of a [`Limit`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    /// No use would ever be allowed.
+    ZeroCapacity,
+    /// Uses would be refilled instantly.
+    ZeroPeriod,
+    /// Text is not of the form `capacity/seconds`.
+    Invalid(String),
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::ZeroCapacity => write!(f, "capacity of a limit must not be zero"),
+            LimitError::ZeroPeriod => write!(f, "period of a limit must not be zero"),
+            LimitError::Invalid(value) => {
+                write!(
+                    f,
+                    "expected a limit of the form `capacity/seconds`, got `{}`",
+                    value
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serenity::model::prelude::*;
+
+    use super::{Limit, LimitError, RateLimitConfig, RateLimited, RateLimiter};
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig {
+            user_commands: "2/10".parse().unwrap(),
+            channel_commands: "3/10".parse().unwrap(),
+            guild_commands: "10/10".parse().unwrap(),
+            max_reactions_per_request: 3,
+            user_reactions: "4/60".parse().unwrap(),
+        }
+    }
+
+    /// Users and channels have their own cooldowns, refused commands
+    /// are not counted.
+    #[test]
+    fn rate_limit_01() {
+        let mut rate_limiter = RateLimiter::new(config());
+        let now = Instant::now();
+        let check = |rate_limiter: &mut RateLimiter, user_id, channel_id, now| {
+            rate_limiter.check_command(
+                UserId(user_id),
+                ChannelId(channel_id),
+                Some(GuildId(1)),
+                now,
+            )
+        };
+
+        assert!(check(&mut rate_limiter, 1, 1, now).is_ok());
+        assert!(check(&mut rate_limiter, 1, 1, now).is_ok());
+        assert_eq!(
+            check(&mut rate_limiter, 1, 1, now),
+            Err(RateLimited::User(Duration::from_secs(5)))
+        );
+        assert!(check(&mut rate_limiter, 2, 1, now).is_ok());
+        assert!(matches!(
+            check(&mut rate_limiter, 3, 1, now),
+            Err(RateLimited::Channel(_))
+        ));
+        assert!(check(&mut rate_limiter, 3, 2, now).is_ok());
+
+        let later = now + Duration::from_secs(5);
+        assert!(check(&mut rate_limiter, 1, 2, later).is_ok());
+        assert!(check(&mut rate_limiter, 1, 2, later).is_err());
+    }
+
+    /// Reactions are capped per request and per user.
+    #[test]
+    fn rate_limit_02() {
+        let mut rate_limiter = RateLimiter::new(config());
+        let now = Instant::now();
+
+        assert_eq!(
+            rate_limiter.check_reactions(UserId(1), 4, now),
+            Err(RateLimited::TooManyReactions(3))
+        );
+        assert!(rate_limiter.check_reactions(UserId(1), 3, now).is_ok());
+        assert_eq!(
+            rate_limiter.check_reactions(UserId(1), 2, now),
+            Err(RateLimited::Reactions(Duration::from_secs(15)))
+        );
+        assert!(rate_limiter.check_reactions(UserId(1), 1, now).is_ok());
+        assert!(rate_limiter.check_reactions(UserId(2), 3, now).is_ok());
+    }
+
+    /// Limits that would never allow a use or never refill are
+    /// refused.
+    #[test]
+    fn rate_limit_03() {
+        assert_eq!(
+            " 5 / 30 ".parse::<Limit>(),
+            Limit::new(5, Duration::from_secs(30))
+        );
+        assert_eq!("0/30".parse::<Limit>(), Err(LimitError::ZeroCapacity));
+        assert_eq!("5/0".parse::<Limit>(), Err(LimitError::ZeroPeriod));
+        assert!(matches!("5".parse::<Limit>(), Err(LimitError::Invalid(_))));
+        assert!(matches!(
+            "-1/30".parse::<Limit>(),
+            Err(LimitError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,597 @@
+//! Tracker of the reactions added by the bot on behalf of users.
+//!
+//! A reaction is removed once the user reacted with it too, or after
+//! the reaction timeout of the [`GuildConfig`] if they did not. The
+//! tracked reactions are owned by a single task, driven by the
+//! messages of the event handler and of the expiry timers. The
+//! configuration is sent along with the reactions to track, so the
+//! task never holds a copy that could go stale.
+//!
+//! Tracked reactions are kept in the [`Storage`](crate::storage::Storage)
+//! too, so the ones left behind when the bot stopped are removed once
+//! it is ready again.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use serenity::{http::Http, model::prelude::*, prelude::Context};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    config::{GuildConfig, RemovalNotice},
+    storage::{PendingReaction, StorageWriter},
+};
+
+/// Reactions added by the bot on behalf of a user to a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotAddedReactions {
+    /// [`GuildId`].
+    pub guild_id: GuildId,
+    /// [`ChannelId`].
+    pub channel_id: ChannelId,
+    /// [`MessageId`].
+    pub message_id: MessageId,
+    /// [`UserId`].
+    pub user_id: UserId,
+    /// Set of [`ReactionType`]s for this particular message.
+    pub reaction_types: HashSet<ReactionType>,
+}
+
+/// Handle to the reaction tracker task.
+#[derive(Debug, Clone)]
+pub struct ReactionTracker {
+    /// Sender of the messages to the task.
+    sender: mpsc::UnboundedSender<TrackerMessage>,
+}
+
+impl ReactionTracker {
+    /// Default time the user has to react before the reactions are
+    /// removed.
+    pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Spawn the tracker task, keeping the tracked reactions in the
+    /// storage. `stale_reactions` are the ones left behind when the bot
+    /// stopped. Must be called within a Tokio runtime.
+    pub fn spawn(storage: StorageWriter, stale_reactions: Vec<PendingReaction>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        tokio::spawn(run(
+            sender.downgrade(),
+            receiver,
+            storage,
+            stale_reactions,
+            TrackerState::default(),
+        ));
+        Self { sender }
+    }
+
+    /// Track the reactions added on behalf of `user`, removing them as
+    /// configured by the [`GuildConfig`] of their guild. Returns right
+    /// away, the reactions expire in the tracker task so commands
+    /// respond without waiting for the reaction timeout.
+    pub fn track(
+        &self,
+        context: &Context,
+        user: &User,
+        reactions: BotAddedReactions,
+        guild_config: GuildConfig,
+    ) {
+        self.send(TrackerMessage::Track {
+            reactions,
+            guild_config,
+            user: user.clone(),
+            bot_id: context.cache.current_user_id(),
+            http: context.http.clone(),
+        });
+    }
+
+    /// Stop tracking the reaction the user reacted with, returning
+    /// `true` if the bot added it on their behalf and so should remove
+    /// its own reaction.
+    pub async fn reaction_added(&self, reaction: &Reaction) -> bool {
+        let user_id = match reaction.user_id {
+            Some(user_id) => user_id,
+            None => return false,
+        };
+
+        let (reply, response) = oneshot::channel();
+        self.send(TrackerMessage::ReactionAdded {
+            channel_id: reaction.channel_id,
+            message_id: reaction.message_id,
+            user_id,
+            reaction_type: reaction.emoji.clone(),
+            reply,
+        });
+        response.await.unwrap_or(false)
+    }
+
+    /// Stop tracking the reactions in the guild the bot left.
+    pub fn forget_guild(&self, guild_id: GuildId) {
+        self.send(TrackerMessage::ForgetGuild(guild_id));
+    }
+
+    /// Remove the reactions that were still tracked when the bot
+    /// stopped, only the first call does anything.
+    pub fn remove_stale_reactions(&self, context: &Context) {
+        self.send(TrackerMessage::RemoveStale {
+            bot_id: context.cache.current_user_id(),
+            http: context.http.clone(),
+        });
+    }
+
+    /// Send the message to the task.
+    fn send(&self, message: TrackerMessage) {
+        if self.sender.send(message).is_err() {
+            tracing::error!("reaction tracker is not running");
+        }
+    }
+}
+
+/// Messages handled by the tracker task.
+enum TrackerMessage {
+    /// Track the reactions, see [`ReactionTracker::track()`].
+    Track {
+        reactions: BotAddedReactions,
+        guild_config: GuildConfig,
+        user: User,
+        bot_id: UserId,
+        http: Arc<Http>,
+    },
+    /// User reacted, see [`ReactionTracker::reaction_added()`].
+    ReactionAdded {
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+        reaction_type: ReactionType,
+        reply: oneshot::Sender<bool>,
+    },
+    /// Timeout of the tracked reactions elapsed.
+    Expire {
+        id: TrackingId,
+        user: User,
+        bot_id: UserId,
+        http: Arc<Http>,
+        guild_config: GuildConfig,
+    },
+    /// Bot left the guild.
+    ForgetGuild(GuildId),
+    /// Bot is ready, see [`ReactionTracker::remove_stale_reactions()`].
+    RemoveStale { bot_id: UserId, http: Arc<Http> },
+}
+
+/// Run the tracker task until all the [`ReactionTracker`]s are
+/// dropped.
+async fn run(
+    sender: mpsc::WeakUnboundedSender<TrackerMessage>,
+    mut receiver: mpsc::UnboundedReceiver<TrackerMessage>,
+    storage: StorageWriter,
+    stale_reactions: Vec<PendingReaction>,
+    mut state: TrackerState,
+) {
+    let mut stale_reactions = Some(stale_reactions);
+
+    while let Some(message) = receiver.recv().await {
+        match message {
+            TrackerMessage::Track {
+                reactions,
+                guild_config,
+                user,
+                bot_id,
+                http,
+            } => {
+                store_pending_reactions(&storage, &reactions);
+                let (channel_id, message_id) = (reactions.channel_id, reactions.message_id);
+                let (id, taken_over) = state.track(reactions);
+                taken_over.iter().for_each(|(user_id, reaction_type)| {
+                    storage.remove_pending_reaction(
+                        channel_id,
+                        message_id,
+                        *user_id,
+                        reaction_type.clone(),
+                    );
+                });
+                if let Some(sender) = sender.upgrade() {
+                    tokio::spawn(async move {
+                        tokio::time::sleep(guild_config.reaction_timeout()).await;
+                        // Tracker stopped if the send fails, nothing is
+                        // left to expire.
+                        let _ = sender.send(TrackerMessage::Expire {
+                            id,
+                            user,
+                            bot_id,
+                            http,
+                            guild_config,
+                        });
+                    });
+                }
+            }
+            TrackerMessage::ReactionAdded {
+                channel_id,
+                message_id,
+                user_id,
+                reaction_type,
+                reply,
+            } => {
+                let reacted = state.reaction_added(channel_id, message_id, user_id, &reaction_type);
+                if reacted {
+                    storage.remove_pending_reaction(channel_id, message_id, user_id, reaction_type);
+                }
+                let _ = reply.send(reacted);
+            }
+            TrackerMessage::Expire {
+                id,
+                user,
+                bot_id,
+                http,
+                guild_config,
+            } => match state.expire(id) {
+                Some(reactions) => {
+                    reactions.reaction_types.iter().for_each(|reaction_type| {
+                        storage.remove_pending_reaction(
+                            reactions.channel_id,
+                            reactions.message_id,
+                            reactions.user_id,
+                            reaction_type.clone(),
+                        );
+                    });
+                    tokio::spawn(remove_expired_reactions(
+                        reactions,
+                        user,
+                        bot_id,
+                        http,
+                        guild_config,
+                    ));
+                }
+                None => {
+                    tracing::info!(
+                        "user `{}` has reacted to all reactions added on their behalf",
+                        user.tag(),
+                    );
+                }
+            },
+            TrackerMessage::ForgetGuild(guild_id) => state.forget_guild(guild_id),
+            TrackerMessage::RemoveStale { bot_id, http } => {
+                if let Some(stale_reactions) = stale_reactions.take() {
+                    tokio::spawn(remove_stale_reactions(
+                        stale_reactions,
+                        storage.clone(),
+                        bot_id,
+                        http,
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Keep the tracked reactions in the storage.
+fn store_pending_reactions(storage: &StorageWriter, reactions: &BotAddedReactions) {
+    for reaction_type in &reactions.reaction_types {
+        storage.insert_pending_reaction(PendingReaction {
+            guild_id: reactions.guild_id,
+            channel_id: reactions.channel_id,
+            message_id: reactions.message_id,
+            user_id: reactions.user_id,
+            reaction_type: reaction_type.clone(),
+        });
+    }
+}
+
+/// Remove the reactions left behind when the bot stopped, without
+/// informing the users since their time to react is long gone.
+async fn remove_stale_reactions(
+    stale_reactions: Vec<PendingReaction>,
+    storage: StorageWriter,
+    bot_id: UserId,
+    http: Arc<Http>,
+) {
+    for stale_reaction in stale_reactions {
+        match http
+            .delete_reaction(
+                stale_reaction.channel_id.0,
+                stale_reaction.message_id.0,
+                Some(bot_id.0),
+                &stale_reaction.reaction_type,
+            )
+            .await
+        {
+            Ok(_) => {
+                tracing::info!(
+                    "deleted stale `{}` reaction from message `{}` in channel `{}`",
+                    stale_reaction.reaction_type,
+                    stale_reaction.message_id,
+                    stale_reaction.channel_id,
+                );
+            }
+            Err(err) => {
+                tracing::error!(
+                    "couldn't delete stale `{}` reaction from message `{}` \
+                     in channel `{}` due to `{}`",
+                    stale_reaction.reaction_type,
+                    stale_reaction.message_id,
+                    stale_reaction.channel_id,
+                    err,
+                );
+            }
+        }
+        storage.remove_pending_reaction(
+            stale_reaction.channel_id,
+            stale_reaction.message_id,
+            stale_reaction.user_id,
+            stale_reaction.reaction_type,
+        );
+    }
+}
+
+/// Remove the reactions the user did not react with and inform them
+/// about it as configured.
+async fn remove_expired_reactions(
+    reactions: BotAddedReactions,
+    user: User,
+    bot_id: UserId,
+    http: Arc<Http>,
+    guild_config: GuildConfig,
+) {
+    let user_tag = user.tag();
+    tracing::info!(
+        "attempting to remove reactions because \
+         user `{}` didn't interact with them",
+        user_tag,
+    );
+    for reaction_type in &reactions.reaction_types {
+        match http
+            .delete_reaction(
+                reactions.channel_id.0,
+                reactions.message_id.0,
+                Some(bot_id.0),
+                reaction_type,
+            )
+            .await
+        {
+            Ok(_) => {
+                tracing::info!(
+                    "deleted `{}` reaction from message\
+                     `{}` in channel `{}` succesfully \
+                     because user `{}` didn't react",
+                    reaction_type,
+                    reactions.message_id,
+                    reactions.channel_id,
+                    user_tag,
+                );
+            }
+            Err(err) => {
+                tracing::error!(
+                    "couldn't delete `{}` reaction from \
+                     message `{}` in channel `{}` because \
+                     user `{}` didn't react due to `{}",
+                    reaction_type,
+                    reactions.message_id,
+                    reactions.channel_id,
+                    user_tag,
+                    err,
+                );
+            }
+        }
+    }
+    if guild_config.removal_notice == RemovalNotice::None {
+        return;
+    }
+    match user
+        .direct_message(&http, |create_message| {
+            create_message.content(format!(
+                "Removed reactions \"{}\" for message `{}` since \
+                 you did **not** react within {} seconds.",
+                reactions
+                    .reaction_types
+                    .iter()
+                    .map(|reaction| reaction.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                reactions.message_id,
+                guild_config.reaction_timeout_secs,
+            ))
+        })
+        .await
+    {
+        Ok(_) => {
+            tracing::info!(
+                "informed `{}` about deleting the \
+                 reactions from message `{}` in channel `{}`",
+                user_tag,
+                reactions.message_id,
+                reactions.channel_id
+            );
+        }
+        Err(err) => {
+            tracing::error!(
+                "couldn't inform `{}` about deleting \
+                 the reactions from message `{}` \
+                 in channel `{}` due to `{}`",
+                user_tag,
+                reactions.message_id,
+                reactions.channel_id,
+                err
+            );
+        }
+    }
+}
+
+/// Identifier of the [`BotAddedReactions`] tracked together.
+type TrackingId = u64;
+
+/// Key of a single tracked reaction.
+type ReactionKey = (ChannelId, MessageId, ReactionType);
+
+/// State of the tracker task.
+#[derive(Debug, Default)]
+struct TrackerState {
+    /// [`TrackingId`] of the next tracked reactions.
+    next_id: TrackingId,
+    /// Tracked reactions by their [`TrackingId`].
+    tracked: HashMap<TrackingId, BotAddedReactions>,
+    /// [`TrackingId`] of every tracked reaction.
+    index: HashMap<ReactionKey, TrackingId>,
+}
+
+impl TrackerState {
+    /// Track the reactions, returning their [`TrackingId`] and the
+    /// users whose reactions were taken over.
+    ///
+    /// The bot reacts only once with an emoji, so a reaction already
+    /// tracked for the message is taken over by the latest user.
+    fn track(&mut self, reactions: BotAddedReactions) -> (TrackingId, Vec<(UserId, ReactionType)>) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut taken_over = Vec::new();
+        for reaction_type in &reactions.reaction_types {
+            let key = (
+                reactions.channel_id,
+                reactions.message_id,
+                reaction_type.clone(),
+            );
+            if let Some(previous_id) = self.index.insert(key, id) {
+                if let Some(previous) = self.tracked.get(&previous_id) {
+                    taken_over.push((previous.user_id, reaction_type.clone()));
+                }
+                self.remove_reaction_type(previous_id, reaction_type);
+            }
+        }
+        self.tracked.insert(id, reactions);
+
+        (id, taken_over)
+    }
+
+    /// Stop tracking the reaction the user reacted with, returning
+    /// `true` if it was tracked for them.
+    fn reaction_added(
+        &mut self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+        reaction_type: &ReactionType,
+    ) -> bool {
+        let key = (channel_id, message_id, reaction_type.clone());
+        let id = match self.index.get(&key) {
+            Some(id) => *id,
+            None => return false,
+        };
+        if self
+            .tracked
+            .get(&id)
+            .is_none_or(|reactions| reactions.user_id != user_id)
+        {
+            return false;
+        }
+
+        self.index.remove(&key);
+        self.remove_reaction_type(id, reaction_type);
+        true
+    }
+
+    /// Stop tracking the reactions, returning the ones the user did
+    /// not react with, [`None`] if they reacted with all of them.
+    fn expire(&mut self, id: TrackingId) -> Option<BotAddedReactions> {
+        let reactions = self.tracked.remove(&id)?;
+        reactions.reaction_types.iter().for_each(|reaction_type| {
+            self.index.remove(&(
+                reactions.channel_id,
+                reactions.message_id,
+                reaction_type.clone(),
+            ));
+        });
+        Some(reactions)
+    }
+
+    /// Stop tracking the reactions in the guild, without removing
+    /// them.
+    fn forget_guild(&mut self, guild_id: GuildId) {
+        let ids = self
+            .tracked
+            .iter()
+            .filter(|(_, reactions)| reactions.guild_id == guild_id)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        ids.into_iter().for_each(|id| {
+            self.expire(id);
+        });
+    }
+
+    /// Remove the reaction from the tracked reactions `id`, dropping
+    /// them once empty.
+    fn remove_reaction_type(&mut self, id: TrackingId, reaction_type: &ReactionType) {
+        if let Some(reactions) = self.tracked.get_mut(&id) {
+            reactions.reaction_types.remove(reaction_type);
+            if reactions.reaction_types.is_empty() {
+                self.tracked.remove(&id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use serenity::model::prelude::*;
+
+    use super::{BotAddedReactions, TrackerState};
+
+    fn reactions(user_id: u64, emojis: &[char]) -> BotAddedReactions {
+        BotAddedReactions {
+            guild_id: GuildId(1),
+            channel_id: ChannelId(2),
+            message_id: MessageId(3),
+            user_id: UserId(user_id),
+            reaction_types: emojis.iter().copied().map(ReactionType::from).collect(),
+        }
+    }
+
+    /// Reactions are only cleared by the user they were added for,
+    /// the rest expire.
+    #[test]
+    fn reaction_tracker_01() {
+        let mut state = TrackerState::default();
+        let (id, _) = state.track(reactions(4, &['a', 'b']));
+
+        let a = ReactionType::from('a');
+        assert!(!state.reaction_added(ChannelId(2), MessageId(3), UserId(5), &a));
+        assert!(!state.reaction_added(ChannelId(2), MessageId(9), UserId(4), &a));
+        assert!(state.reaction_added(ChannelId(2), MessageId(3), UserId(4), &a));
+        assert!(!state.reaction_added(ChannelId(2), MessageId(3), UserId(4), &a));
+
+        let expired = state.expire(id).unwrap();
+        assert_eq!(
+            expired.reaction_types,
+            HashSet::from([ReactionType::from('b')])
+        );
+        assert!(state.expire(id).is_none());
+        assert!(state.index.is_empty());
+    }
+
+    /// Reactions tracked again for another user are taken over by
+    /// them, fully reacted reactions no longer expire.
+    #[test]
+    fn reaction_tracker_02() {
+        let mut state = TrackerState::default();
+        let (first, _) = state.track(reactions(4, &['a', 'b']));
+        let (second, taken_over) = state.track(reactions(5, &['b']));
+        assert_eq!(taken_over, [(UserId(4), ReactionType::from('b'))]);
+
+        let b = ReactionType::from('b');
+        assert!(!state.reaction_added(ChannelId(2), MessageId(3), UserId(4), &b));
+        assert!(state.reaction_added(ChannelId(2), MessageId(3), UserId(5), &b));
+        assert!(state.expire(second).is_none());
+        assert_eq!(
+            state.expire(first).unwrap().reaction_types,
+            HashSet::from([ReactionType::from('a')])
+        );
+
+        state.track(reactions(4, &['c']));
+        state.forget_guild(GuildId(1));
+        assert!(state.tracked.is_empty());
+        assert!(state.index.is_empty());
+    }
+}
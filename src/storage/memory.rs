@@ -0,0 +1,176 @@
+//! [`Storage`] keeping everything in memory, nothing survives a
+//! restart.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use serenity::model::prelude::*;
+
+use crate::audit_log::AuditEntry;
+
+use super::{GuildTable, PendingReaction, Storage, StorageError};
+
+/// Key of a [`PendingReaction`].
+type PendingReactionKey = (ChannelId, MessageId, UserId, ReactionType);
+
+/// State of a [`MemoryStorage`].
+#[derive(Debug, Default)]
+struct MemoryState {
+    /// Data of the guilds by table.
+    guild_data: HashMap<GuildTable, BTreeMap<GuildId, String>>,
+    /// Audit log, oldest first.
+    audit_entries: VecDeque<AuditEntry>,
+    /// Pending reactions.
+    pending_reactions: HashMap<PendingReactionKey, PendingReaction>,
+}
+
+/// [`Storage`] in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    /// State, behind a lock since the [`Storage`] is shared.
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryStorage {
+    /// Create an empty [`MemoryStorage`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` with the state locked.
+    fn with_state<T>(&self, f: impl FnOnce(&mut MemoryState) -> T) -> T {
+        // State stays consistent even if a panic poisoned the lock.
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut state)
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn guild_data(&self, table: GuildTable) -> Result<Vec<(GuildId, String)>, StorageError> {
+        Ok(self.with_state(|state| {
+            state
+                .guild_data
+                .get(&table)
+                .map(|guilds| {
+                    guilds
+                        .iter()
+                        .map(|(guild_id, data)| (*guild_id, data.clone()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        }))
+    }
+
+    fn set_guild_data(
+        &self,
+        table: GuildTable,
+        guild_id: GuildId,
+        data: Option<&str>,
+    ) -> Result<(), StorageError> {
+        self.with_state(|state| {
+            let guilds = state.guild_data.entry(table).or_default();
+            match data {
+                Some(data) => guilds.insert(guild_id, data.to_string()),
+                None => guilds.remove(&guild_id),
+            };
+        });
+        Ok(())
+    }
+
+    fn insert_audit_entry(
+        &self,
+        entry: &AuditEntry,
+        max_entries_per_guild: usize,
+    ) -> Result<(), StorageError> {
+        self.with_state(|state| {
+            state.audit_entries.push_back(entry.clone());
+            let count = state
+                .audit_entries
+                .iter()
+                .filter(|other| other.guild_id == entry.guild_id)
+                .count();
+            if count > max_entries_per_guild {
+                if let Some(oldest) = state
+                    .audit_entries
+                    .iter()
+                    .position(|other| other.guild_id == entry.guild_id)
+                {
+                    state.audit_entries.remove(oldest);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn audit_entries(&self) -> Result<Vec<AuditEntry>, StorageError> {
+        Ok(self.with_state(|state| state.audit_entries.iter().cloned().collect()))
+    }
+
+    fn insert_pending_reaction(&self, reaction: &PendingReaction) -> Result<(), StorageError> {
+        self.with_state(|state| {
+            state.pending_reactions.insert(
+                (
+                    reaction.channel_id,
+                    reaction.message_id,
+                    reaction.user_id,
+                    reaction.reaction_type.clone(),
+                ),
+                reaction.clone(),
+            )
+        });
+        Ok(())
+    }
+
+    fn remove_pending_reaction(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+        reaction_type: &ReactionType,
+    ) -> Result<(), StorageError> {
+        self.with_state(|state| {
+            state.pending_reactions.remove(&(
+                channel_id,
+                message_id,
+                user_id,
+                reaction_type.clone(),
+            ))
+        });
+        Ok(())
+    }
+
+    fn pending_reactions(&self) -> Result<Vec<PendingReaction>, StorageError> {
+        Ok(self.with_state(|state| state.pending_reactions.values().cloned().collect()))
+    }
+
+    fn forget_guild(&self, guild_id: GuildId) -> Result<(), StorageError> {
+        self.with_state(|state| {
+            state.guild_data.values_mut().for_each(|guilds| {
+                guilds.remove(&guild_id);
+            });
+            state
+                .audit_entries
+                .retain(|entry| entry.guild_id != guild_id);
+            state
+                .pending_reactions
+                .retain(|_, reaction| reaction.guild_id != guild_id);
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryStorage;
+    use crate::storage::tests::check_storage;
+
+    #[test]
+    fn memory_storage_01() {
+        check_storage(&MemoryStorage::new());
+    }
+}
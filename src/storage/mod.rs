@@ -0,0 +1,289 @@
+//! Storage of the state of the bot that must survive restarts.
+//!
+//! The state lives in memory in the [`BotContext`](crate::BotContext),
+//! every change is written through to the [`Storage`] by the
+//! [`StorageWriter`] and the state is loaded back from it on start.
+//! Message history and custom emojis are fetched from Discord again
+//! instead.
+
+pub mod memory;
+pub mod sqlite;
+pub mod writer;
+
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serenity::model::prelude::*;
+
+use crate::audit_log::AuditEntry;
+
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
+pub use writer::StorageWriter;
+
+/// Per guild state kept in the [`Storage`], stored as JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GuildTable {
+    /// [`GuildSettings`](crate::guild_settings::GuildSettings).
+    Settings,
+    /// [`GuildPolicy`](crate::policy::GuildPolicy).
+    Policies,
+    /// [`ContentFilter`](crate::content_filter::ContentFilter).
+    ContentFilters,
+    /// [`GuildConfig`](crate::config::GuildConfig).
+    Configs,
+    /// [`CommandToggles`](crate::command_toggles::CommandToggles).
+    CommandToggles,
+}
+
+impl GuildTable {
+    /// Name of the table in the storage.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GuildTable::Settings => "settings",
+            GuildTable::Policies => "policies",
+            GuildTable::ContentFilters => "content_filters",
+            GuildTable::Configs => "configs",
+            GuildTable::CommandToggles => "command_toggles",
+        }
+    }
+}
+
+/// Reaction added by the bot on behalf of a user that is not removed
+/// yet, see [`ReactionTracker`](crate::reaction_tracker::ReactionTracker).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingReaction {
+    /// [`GuildId`].
+    pub guild_id: GuildId,
+    /// [`ChannelId`].
+    pub channel_id: ChannelId,
+    /// [`MessageId`].
+    pub message_id: MessageId,
+    /// [`UserId`] the reaction was added for.
+    pub user_id: UserId,
+    /// [`ReactionType`].
+    pub reaction_type: ReactionType,
+}
+
+/// Backend storing the state of the bot.
+///
+/// Calls block, so the state is only read on start and written
+/// through a [`StorageWriter`].
+pub trait Storage: Send + Sync + std::fmt::Debug {
+    /// JSON data of the guilds in the table.
+    fn guild_data(&self, table: GuildTable) -> Result<Vec<(GuildId, String)>, StorageError>;
+
+    /// Set the JSON data of the guild in the table, [`None`] removing
+    /// it.
+    fn set_guild_data(
+        &self,
+        table: GuildTable,
+        guild_id: GuildId,
+        data: Option<&str>,
+    ) -> Result<(), StorageError>;
+
+    /// Append the entry to the audit log, dropping the oldest entries
+    /// of the guild beyond `max_entries_per_guild`.
+    fn insert_audit_entry(
+        &self,
+        entry: &AuditEntry,
+        max_entries_per_guild: usize,
+    ) -> Result<(), StorageError>;
+
+    /// Entries of the audit log, oldest first.
+    fn audit_entries(&self) -> Result<Vec<AuditEntry>, StorageError>;
+
+    /// Store the pending reaction, replacing the one of the same
+    /// message, user and [`ReactionType`].
+    fn insert_pending_reaction(&self, reaction: &PendingReaction) -> Result<(), StorageError>;
+
+    /// Remove the pending reaction of the message added for the user.
+    fn remove_pending_reaction(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+        reaction_type: &ReactionType,
+    ) -> Result<(), StorageError>;
+
+    /// All the pending reactions.
+    fn pending_reactions(&self) -> Result<Vec<PendingReaction>, StorageError>;
+
+    /// Remove everything stored for the guild.
+    fn forget_guild(&self, guild_id: GuildId) -> Result<(), StorageError>;
+}
+
+impl dyn Storage {
+    /// Load the table, skipping the guilds whose data cannot be
+    /// parsed.
+    pub fn load<T: DeserializeOwned>(
+        &self,
+        table: GuildTable,
+    ) -> Result<HashMap<GuildId, T>, StorageError> {
+        Ok(self
+            .guild_data(table)?
+            .into_iter()
+            .filter_map(|(guild_id, data)| match serde_json::from_str(&data) {
+                Ok(value) => Some((guild_id, value)),
+                Err(err) => {
+                    tracing::error!(
+                        "couldn't parse `{}` of guild `{}` due to `{}`",
+                        table.name(),
+                        guild_id,
+                        err
+                    );
+                    None
+                }
+            })
+            .collect())
+    }
+}
+
+/// Errors of a [`Storage`].
+#[derive(Debug)]
+pub enum StorageError {
+    /// SQLite database failed.
+    Sqlite(rusqlite::Error),
+    /// Stored value could not be serialized or deserialized.
+    Json(serde_json::Error),
+    /// Stored reaction is not a valid [`ReactionType`].
+    InvalidReaction(String),
+    /// Database was created by a newer version of the bot.
+    UnknownSchemaVersion(u32),
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(err: rusqlite::Error) -> Self {
+        StorageError::Sqlite(err)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(err: serde_json::Error) -> Self {
+        StorageError::Json(err)
+    }
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "storage: ")?;
+        match self {
+            StorageError::Sqlite(err) => write!(f, "{}", err),
+            StorageError::Json(err) => write!(f, "{}", err),
+            StorageError::InvalidReaction(reaction) => {
+                write!(f, "invalid reaction `{}`", reaction)
+            }
+            StorageError::UnknownSchemaVersion(version) => {
+                write!(f, "schema version {} is newer than this bot", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serenity::model::{prelude::*, Timestamp};
+
+    use crate::audit_log::{AuditEntry, AuditOutcome};
+
+    use super::{GuildTable, PendingReaction, Storage};
+
+    fn audit_entry(guild_id: u64, message_id: u64) -> AuditEntry {
+        AuditEntry {
+            timestamp: Timestamp::from_unix_timestamp(1_700_000_000).unwrap(),
+            guild_id: GuildId(guild_id),
+            channel_id: ChannelId(2),
+            message_id: MessageId(message_id),
+            user_id: UserId(3),
+            user_tag: "user#3".to_string(),
+            command: "/add_reaction emoji \"👍\"".to_string(),
+            emojis: "👍".to_string(),
+            outcome: AuditOutcome::default(),
+        }
+    }
+
+    fn pending_reaction(guild_id: u64, user_id: u64) -> PendingReaction {
+        PendingReaction {
+            guild_id: GuildId(guild_id),
+            channel_id: ChannelId(2),
+            message_id: MessageId(guild_id),
+            user_id: UserId(user_id),
+            reaction_type: ReactionType::Unicode("👍".to_string()),
+        }
+    }
+
+    /// Behaviour every [`Storage`] must have.
+    pub(crate) fn check_storage(storage: &dyn Storage) {
+        storage
+            .set_guild_data(GuildTable::Settings, GuildId(1), Some("{}"))
+            .unwrap();
+        storage
+            .set_guild_data(GuildTable::Settings, GuildId(1), Some("[]"))
+            .unwrap();
+        storage
+            .set_guild_data(GuildTable::Policies, GuildId(2), Some("{}"))
+            .unwrap();
+        assert_eq!(
+            storage.guild_data(GuildTable::Settings).unwrap(),
+            [(GuildId(1), "[]".to_string())]
+        );
+        storage
+            .set_guild_data(GuildTable::Policies, GuildId(2), None)
+            .unwrap();
+        assert!(storage.guild_data(GuildTable::Policies).unwrap().is_empty());
+
+        (1..=3).for_each(|message_id| {
+            storage
+                .insert_audit_entry(&audit_entry(1, message_id), 2)
+                .unwrap()
+        });
+        storage.insert_audit_entry(&audit_entry(2, 4), 2).unwrap();
+        assert_eq!(
+            storage.audit_entries().unwrap(),
+            [audit_entry(1, 2), audit_entry(1, 3), audit_entry(2, 4)]
+        );
+
+        storage
+            .insert_pending_reaction(&pending_reaction(1, 1))
+            .unwrap();
+        storage
+            .insert_pending_reaction(&pending_reaction(1, 2))
+            .unwrap();
+        storage
+            .insert_pending_reaction(&pending_reaction(1, 2))
+            .unwrap();
+        storage
+            .insert_pending_reaction(&pending_reaction(2, 1))
+            .unwrap();
+        let mut pending_reactions = storage.pending_reactions().unwrap();
+        pending_reactions.sort_by_key(|reaction| (reaction.guild_id, reaction.user_id));
+        assert_eq!(
+            pending_reactions,
+            [
+                pending_reaction(1, 1),
+                pending_reaction(1, 2),
+                pending_reaction(2, 1)
+            ]
+        );
+        let reaction = pending_reaction(1, 1);
+        storage
+            .remove_pending_reaction(
+                reaction.channel_id,
+                reaction.message_id,
+                reaction.user_id,
+                &reaction.reaction_type,
+            )
+            .unwrap();
+        assert_eq!(storage.pending_reactions().unwrap().len(), 2);
+
+        storage.forget_guild(GuildId(1)).unwrap();
+        assert!(storage.guild_data(GuildTable::Settings).unwrap().is_empty());
+        assert_eq!(storage.audit_entries().unwrap(), [audit_entry(2, 4)]);
+        assert_eq!(
+            storage.pending_reactions().unwrap(),
+            [pending_reaction(2, 1)]
+        );
+    }
+}
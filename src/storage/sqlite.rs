@@ -0,0 +1,346 @@
+//! [`Storage`] in an SQLite database file.
+
+use std::{path::Path, sync::Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serenity::model::prelude::*;
+
+use crate::audit_log::AuditEntry;
+
+use super::{GuildTable, PendingReaction, Storage, StorageError};
+
+/// Migrations of the schema, the version of the schema is the number
+/// of migrations applied. Released migrations must never change, new
+/// ones are appended.
+const MIGRATIONS: &[&str] = &[
+    // 1: guild data, audit log and pending reactions.
+    "CREATE TABLE guild_data (
+        guild_table TEXT NOT NULL,
+        guild_id INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (guild_table, guild_id)
+    );
+    CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        guild_id INTEGER NOT NULL,
+        entry TEXT NOT NULL
+    );
+    CREATE INDEX audit_log_guild_id ON audit_log (guild_id, id);
+    CREATE TABLE pending_reactions (
+        channel_id INTEGER NOT NULL,
+        message_id INTEGER NOT NULL,
+        reaction TEXT NOT NULL,
+        guild_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        PRIMARY KEY (channel_id, message_id, reaction)
+    );",
+    // 2: pending reactions of several users for the same emoji.
+    "CREATE TABLE pending_reactions_by_user (
+        channel_id INTEGER NOT NULL,
+        message_id INTEGER NOT NULL,
+        reaction TEXT NOT NULL,
+        guild_id INTEGER NOT NULL,
+        user_id INTEGER NOT NULL,
+        PRIMARY KEY (channel_id, message_id, user_id, reaction)
+    );
+    INSERT INTO pending_reactions_by_user
+        SELECT channel_id, message_id, reaction, guild_id, user_id FROM pending_reactions;
+    DROP TABLE pending_reactions;
+    ALTER TABLE pending_reactions_by_user RENAME TO pending_reactions;",
+];
+
+/// Snowflake as stored in SQLite, which only has signed integers.
+fn to_sql_id(id: u64) -> i64 {
+    id as i64
+}
+
+/// Snowflake stored with [`to_sql_id()`].
+fn from_sql_id(id: i64) -> u64 {
+    id as u64
+}
+
+/// [`Storage`] in SQLite.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    /// Connection to the database.
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Open the database at the path, creating it if needed, and
+    /// migrate it to the latest schema.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        Self::new(Connection::open(path)?)
+    }
+
+    /// Open a database that lives in memory, for testing.
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    /// Migrate the database of the connection.
+    fn new(mut connection: Connection) -> Result<Self, StorageError> {
+        migrate(&mut connection)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Version of the schema of the database.
+    pub fn schema_version(&self) -> Result<u32, StorageError> {
+        self.with_connection(|connection| schema_version(connection))
+    }
+
+    /// Run `f` with the connection locked.
+    fn with_connection<T>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> Result<T, StorageError>,
+    ) -> Result<T, StorageError> {
+        let mut connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut connection)
+    }
+}
+
+/// Version of the schema, see [`MIGRATIONS`].
+fn schema_version(connection: &Connection) -> Result<u32, StorageError> {
+    Ok(connection.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+/// Apply the migrations the database is missing, each in its own
+/// transaction.
+fn migrate(connection: &mut Connection) -> Result<(), StorageError> {
+    let version = schema_version(connection)?;
+    if version as usize > MIGRATIONS.len() {
+        return Err(StorageError::UnknownSchemaVersion(version));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+        tracing::info!("migrated storage to schema version {}", index + 1);
+    }
+    Ok(())
+}
+
+impl Storage for SqliteStorage {
+    fn guild_data(&self, table: GuildTable) -> Result<Vec<(GuildId, String)>, StorageError> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare_cached(
+                "SELECT guild_id, data FROM guild_data WHERE guild_table = ?1 ORDER BY guild_id",
+            )?;
+            let rows = statement.query_map(params![table.name()], |row| {
+                Ok((GuildId(from_sql_id(row.get(0)?)), row.get(1)?))
+            })?;
+            Ok(rows.collect::<Result<_, _>>()?)
+        })
+    }
+
+    fn set_guild_data(
+        &self,
+        table: GuildTable,
+        guild_id: GuildId,
+        data: Option<&str>,
+    ) -> Result<(), StorageError> {
+        self.with_connection(|connection| {
+            match data {
+                Some(data) => connection.execute(
+                    "INSERT INTO guild_data (guild_table, guild_id, data) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (guild_table, guild_id) DO UPDATE SET data = excluded.data",
+                    params![table.name(), to_sql_id(guild_id.0), data],
+                )?,
+                None => connection.execute(
+                    "DELETE FROM guild_data WHERE guild_table = ?1 AND guild_id = ?2",
+                    params![table.name(), to_sql_id(guild_id.0)],
+                )?,
+            };
+            Ok(())
+        })
+    }
+
+    fn insert_audit_entry(
+        &self,
+        entry: &AuditEntry,
+        max_entries_per_guild: usize,
+    ) -> Result<(), StorageError> {
+        let data = serde_json::to_string(entry)?;
+        self.with_connection(|connection| {
+            let transaction = connection.transaction()?;
+            transaction.execute(
+                "INSERT INTO audit_log (guild_id, entry) VALUES (?1, ?2)",
+                params![to_sql_id(entry.guild_id.0), data],
+            )?;
+            let oldest_kept: Option<i64> = transaction
+                .query_row(
+                    "SELECT id FROM audit_log WHERE guild_id = ?1
+                     ORDER BY id DESC LIMIT 1 OFFSET ?2",
+                    params![
+                        to_sql_id(entry.guild_id.0),
+                        max_entries_per_guild.saturating_sub(1) as i64
+                    ],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(oldest_kept) = oldest_kept {
+                transaction.execute(
+                    "DELETE FROM audit_log WHERE guild_id = ?1 AND id < ?2",
+                    params![to_sql_id(entry.guild_id.0), oldest_kept],
+                )?;
+            }
+            transaction.commit()?;
+            Ok(())
+        })
+    }
+
+    fn audit_entries(&self) -> Result<Vec<AuditEntry>, StorageError> {
+        self.with_connection(|connection| {
+            let mut statement =
+                connection.prepare_cached("SELECT entry FROM audit_log ORDER BY id")?;
+            let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+            rows.map(|data| Ok(serde_json::from_str(&data?)?)).collect()
+        })
+    }
+
+    fn insert_pending_reaction(&self, reaction: &PendingReaction) -> Result<(), StorageError> {
+        self.with_connection(|connection| {
+            connection.execute(
+                "INSERT OR REPLACE INTO pending_reactions
+                 (channel_id, message_id, reaction, guild_id, user_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    to_sql_id(reaction.channel_id.0),
+                    to_sql_id(reaction.message_id.0),
+                    reaction.reaction_type.to_string(),
+                    to_sql_id(reaction.guild_id.0),
+                    to_sql_id(reaction.user_id.0),
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn remove_pending_reaction(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+        reaction_type: &ReactionType,
+    ) -> Result<(), StorageError> {
+        self.with_connection(|connection| {
+            connection.execute(
+                "DELETE FROM pending_reactions
+                 WHERE channel_id = ?1 AND message_id = ?2 AND user_id = ?3 AND reaction = ?4",
+                params![
+                    to_sql_id(channel_id.0),
+                    to_sql_id(message_id.0),
+                    to_sql_id(user_id.0),
+                    reaction_type.to_string(),
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn pending_reactions(&self) -> Result<Vec<PendingReaction>, StorageError> {
+        self.with_connection(|connection| {
+            let mut statement = connection.prepare_cached(
+                "SELECT guild_id, channel_id, message_id, user_id, reaction
+                 FROM pending_reactions",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?;
+            rows.map(|row| {
+                let (guild_id, channel_id, message_id, user_id, reaction) = row?;
+                Ok(PendingReaction {
+                    guild_id: GuildId(from_sql_id(guild_id)),
+                    channel_id: ChannelId(from_sql_id(channel_id)),
+                    message_id: MessageId(from_sql_id(message_id)),
+                    user_id: UserId(from_sql_id(user_id)),
+                    reaction_type: ReactionType::try_from(reaction.as_str())
+                        .map_err(|_| StorageError::InvalidReaction(reaction))?,
+                })
+            })
+            .collect()
+        })
+    }
+
+    fn forget_guild(&self, guild_id: GuildId) -> Result<(), StorageError> {
+        self.with_connection(|connection| {
+            let transaction = connection.transaction()?;
+            for table in ["guild_data", "audit_log", "pending_reactions"] {
+                transaction.execute(
+                    &format!("DELETE FROM {} WHERE guild_id = ?1", table),
+                    params![to_sql_id(guild_id.0)],
+                )?;
+            }
+            transaction.commit()?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::{params, Connection};
+
+    use serenity::model::prelude::*;
+
+    use super::{migrate, SqliteStorage, MIGRATIONS};
+    use crate::storage::{tests::check_storage, Storage, StorageError};
+
+    #[test]
+    fn sqlite_storage_01() {
+        check_storage(&SqliteStorage::open_in_memory().unwrap());
+    }
+
+    /// Migrations run once and newer schemas are refused.
+    #[test]
+    fn sqlite_storage_02() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        assert_eq!(storage.schema_version().unwrap() as usize, MIGRATIONS.len());
+
+        let mut connection = Connection::open_in_memory().unwrap();
+        migrate(&mut connection).unwrap();
+        migrate(&mut connection).unwrap();
+
+        connection
+            .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        assert!(matches!(
+            migrate(&mut connection),
+            Err(StorageError::UnknownSchemaVersion(_))
+        ));
+    }
+
+    /// Pending reactions are kept when their key gains the user.
+    #[test]
+    fn sqlite_storage_03() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        let transaction = connection.transaction().unwrap();
+        transaction.execute_batch(MIGRATIONS[0]).unwrap();
+        transaction.pragma_update(None, "user_version", 1).unwrap();
+        transaction
+            .execute(
+                "INSERT INTO pending_reactions VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![2, 3, "👍", 1, 4],
+            )
+            .unwrap();
+        transaction.commit().unwrap();
+
+        let storage = SqliteStorage::new(connection).unwrap();
+        let pending_reactions = storage.pending_reactions().unwrap();
+        assert_eq!(pending_reactions.len(), 1);
+        assert_eq!(pending_reactions[0].user_id, UserId(4));
+    }
+}
@@ -0,0 +1,137 @@
+//! Writes to a [`Storage`] made on a dedicated thread.
+
+use std::sync::{mpsc, Arc};
+
+use serde::Serialize;
+use serenity::model::prelude::*;
+
+use crate::audit_log::AuditEntry;
+
+use super::{GuildTable, PendingReaction, Storage, StorageError};
+
+/// Write queued for the thread of a [`StorageWriter`].
+type Write = Box<dyn FnOnce(&dyn Storage) + Send>;
+
+/// Handle to the thread writing to the [`Storage`].
+///
+/// Storage calls block, so the async tasks only queue the writes and
+/// never wait for them. Writes are made in the order they are queued,
+/// errors are logged since the state in memory stays authoritative.
+#[derive(Debug, Clone)]
+pub struct StorageWriter {
+    /// Sender of the writes to the thread.
+    sender: mpsc::Sender<Write>,
+}
+
+impl StorageWriter {
+    /// Spawn the thread writing to the storage, it stops once all the
+    /// [`StorageWriter`]s are dropped.
+    pub fn spawn(storage: Arc<dyn Storage>) -> Self {
+        let (sender, receiver) = mpsc::channel::<Write>();
+        std::thread::Builder::new()
+            .name("storage".to_string())
+            .spawn(move || receiver.into_iter().for_each(|write| write(&*storage)))
+            .expect("couldn't spawn the storage thread");
+        Self { sender }
+    }
+
+    /// Queue the write.
+    fn write(&self, write: impl FnOnce(&dyn Storage) + Send + 'static) {
+        if self.sender.send(Box::new(write)).is_err() {
+            tracing::error!("storage thread is not running");
+        }
+    }
+
+    /// Save the value of the guild in the table, [`None`] removing it.
+    pub fn save<T: Serialize>(&self, table: GuildTable, guild_id: GuildId, value: Option<&T>) {
+        let data = match value.map(serde_json::to_string).transpose() {
+            Ok(data) => data,
+            Err(err) => {
+                tracing::error!(
+                    "couldn't serialize `{}` of guild `{}` due to `{}`",
+                    table.name(),
+                    guild_id,
+                    err
+                );
+                return;
+            }
+        };
+        self.write(move |storage| {
+            if let Err(err) = storage.set_guild_data(table, guild_id, data.as_deref()) {
+                tracing::error!(
+                    "couldn't save `{}` of guild `{}` due to `{}`",
+                    table.name(),
+                    guild_id,
+                    err
+                );
+            }
+        });
+    }
+
+    /// Append the entry to the audit log, see
+    /// [`Storage::insert_audit_entry()`].
+    pub fn insert_audit_entry(&self, entry: AuditEntry, max_entries_per_guild: usize) {
+        self.write(move |storage| {
+            if let Err(err) = storage.insert_audit_entry(&entry, max_entries_per_guild) {
+                tracing::error!(
+                    "couldn't store audit entry of guild `{}` due to `{}`",
+                    entry.guild_id,
+                    err
+                );
+            }
+        });
+    }
+
+    /// Store the pending reaction.
+    pub fn insert_pending_reaction(&self, reaction: PendingReaction) {
+        self.write(move |storage| {
+            if let Err(err) = storage.insert_pending_reaction(&reaction) {
+                log_pending_reaction_error(&reaction.reaction_type, reaction.message_id, err);
+            }
+        });
+    }
+
+    /// Remove the pending reaction of the message added for the user.
+    pub fn remove_pending_reaction(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        user_id: UserId,
+        reaction_type: ReactionType,
+    ) {
+        self.write(move |storage| {
+            if let Err(err) =
+                storage.remove_pending_reaction(channel_id, message_id, user_id, &reaction_type)
+            {
+                log_pending_reaction_error(&reaction_type, message_id, err);
+            }
+        });
+    }
+
+    /// Remove everything stored for the guild.
+    pub fn forget_guild(&self, guild_id: GuildId) {
+        self.write(move |storage| {
+            if let Err(err) = storage.forget_guild(guild_id) {
+                tracing::error!(
+                    "couldn't remove guild `{}` from the storage due to `{}`",
+                    guild_id,
+                    err
+                );
+            }
+        });
+    }
+}
+
+/// Log the error of storing or removing the pending reaction.
+fn log_pending_reaction_error(
+    reaction_type: &ReactionType,
+    message_id: MessageId,
+    err: StorageError,
+) {
+    tracing::error!(
+        "couldn't update pending reaction `{}` on message `{}` due to `{}`",
+        reaction_type,
+        message_id,
+        err
+    );
+}